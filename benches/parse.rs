@@ -0,0 +1,146 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use eventsource_stream::{Event, Eventsource};
+use futures::executor::block_on;
+use futures::stream::{self, StreamExt};
+
+/// The `split_off` per line loop over nom's streaming parsers that `EventStream` used before the
+/// cursor based line scanner, kept here as a baseline
+mod legacy {
+    use nom::branch::alt;
+    use nom::bytes::streaming::{tag, take_while, take_while1, take_while_m_n};
+    use nom::combinator::opt;
+    use nom::sequence::{preceded, terminated, tuple};
+    use nom::IResult;
+
+    enum RawEventLine<'a> {
+        Comment,
+        Field(&'a str, Option<&'a str>),
+        Empty,
+    }
+
+    fn is_any_char(c: char) -> bool {
+        c != '\r' && c != '\n'
+    }
+
+    fn is_name_char(c: char) -> bool {
+        is_any_char(c) && c != ':'
+    }
+
+    fn end_of_line(input: &str) -> IResult<&str, &str> {
+        alt((
+            tag("\r\n"),
+            take_while_m_n(1, 1, |c| c == '\r'),
+            take_while_m_n(1, 1, |c| c == '\n'),
+        ))(input)
+    }
+
+    fn comment(input: &str) -> IResult<&str, RawEventLine<'_>> {
+        preceded(
+            take_while_m_n(1, 1, |c| c == ':'),
+            terminated(take_while(is_any_char), end_of_line),
+        )(input)
+        .map(|(input, _)| (input, RawEventLine::Comment))
+    }
+
+    fn field(input: &str) -> IResult<&str, RawEventLine<'_>> {
+        terminated(
+            tuple((
+                take_while1(is_name_char),
+                opt(preceded(
+                    take_while_m_n(1, 1, |c| c == ':'),
+                    preceded(
+                        opt(take_while_m_n(1, 1, |c| c == ' ')),
+                        take_while(is_any_char),
+                    ),
+                )),
+            )),
+            end_of_line,
+        )(input)
+        .map(|(input, (field, data))| (input, RawEventLine::Field(field, data)))
+    }
+
+    fn empty(input: &str) -> IResult<&str, RawEventLine<'_>> {
+        end_of_line(input).map(|(i, _)| (i, RawEventLine::Empty))
+    }
+
+    fn line(input: &str) -> IResult<&str, RawEventLine<'_>> {
+        alt((comment, field, empty))(input)
+    }
+
+    pub fn parse(mut buffer: String) -> usize {
+        let mut data = String::new();
+        let mut events = 0;
+        while let Ok((rem, next_line)) = line(&buffer) {
+            match next_line {
+                RawEventLine::Field("data", val) => {
+                    data.push_str(val.unwrap_or(""));
+                    data.push('\n');
+                }
+                RawEventLine::Empty if !data.is_empty() => {
+                    data.clear();
+                    events += 1;
+                }
+                _ => {}
+            }
+            let consumed = buffer.len() - rem.len();
+            buffer = buffer.split_off(consumed);
+        }
+        events
+    }
+}
+
+fn many_lines(lines: usize) -> String {
+    let mut input = String::new();
+    for i in 0..lines {
+        input.push_str("data: ");
+        input.push_str(&i.to_string());
+        input.push_str(" lorem ipsum dolor sit amet\n");
+    }
+    input.push('\n');
+    input
+}
+
+fn event_stream(chunks: &[String]) -> Vec<Event> {
+    block_on(
+        stream::iter(chunks.iter().map(Ok::<_, ()>))
+            .eventsource()
+            .map(Result::unwrap)
+            .collect(),
+    )
+}
+
+fn single_chunk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_chunk");
+    for lines in [100, 1_000, 10_000] {
+        let input = many_lines(lines);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("event_stream", lines),
+            &input,
+            |b, input| {
+                let chunks = vec![input.clone()];
+                b.iter(|| event_stream(&chunks))
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("legacy", lines), &input, |b, input| {
+            b.iter(|| legacy::parse(input.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn small_chunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_chunks");
+    let input = many_lines(10_000);
+    let chunks = input
+        .as_bytes()
+        .chunks(1024)
+        .map(|chunk| String::from_utf8(chunk.to_vec()).unwrap())
+        .collect::<Vec<_>>();
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("event_stream", |b| b.iter(|| event_stream(&chunks)));
+    group.finish();
+}
+
+criterion_group!(benches, single_chunk, small_chunks);
+criterion_main!(benches);
@@ -0,0 +1,178 @@
+use crate::encode_stream::EncodeStream;
+use crate::encoder::{check, EncodeError};
+use crate::event::{Event, Frame};
+use crate::framework::{comment_frame, parse_frame, render, NativeEventError, Once};
+use crate::response::RESPONSE_HEADERS;
+use crate::timer::Timer;
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::{HttpRequest, HttpResponse, Responder};
+use actix_web_lab::sse;
+use bytes::Bytes;
+use core::convert::{Infallible, TryFrom};
+use core::pin::Pin;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+
+/// Sent in chunks like the `http_body::Body` of an [`EncodeStream`]
+impl<S, T, Tm> MessageBody for EncodeStream<S, Tm>
+where
+    S: Stream<Item = Event<T>>,
+    T: AsRef<str>,
+    Tm: Timer,
+{
+    type Error = EncodeError;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Bytes, EncodeError>>> {
+        Stream::poll_next(self, cx)
+    }
+}
+
+/// Respond with the encoded events, with the headers set by [`crate::event_stream_response`]
+///
+/// ```
+/// use actix_web::Responder;
+/// use eventsource_stream::{EncodeEventsource, Event};
+///
+/// async fn handler() -> impl Responder {
+///     futures::stream::iter(vec![Event {
+///         data: "Hello, world!".to_string(),
+///         ..Default::default()
+///     }])
+///     .encode_eventsource()
+/// }
+/// ```
+impl<S, T, Tm> Responder for EncodeStream<S, Tm>
+where
+    S: Stream<Item = Event<T>> + 'static,
+    T: AsRef<str> + 'static,
+    Tm: Timer + 'static,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<BoxBody> {
+        let mut response = HttpResponse::Ok();
+        for (name, value) in RESPONSE_HEADERS {
+            response.insert_header((name.as_str(), value));
+        }
+        response.body(self)
+    }
+}
+
+/// Convert an event into actix-web-lab's event type. Events with a reconnection time or
+/// extension fields fail with [`EncodeError::UnsupportedField`], as its events can't carry
+/// them.
+impl<T: AsRef<str>> TryFrom<Event<T>> for sse::Event {
+    type Error = EncodeError;
+
+    fn try_from(event: Event<T>) -> Result<Self, EncodeError> {
+        check(&event)?;
+        if event.retry.is_some() || !event.extensions.is_empty() {
+            return Err(EncodeError::UnsupportedField);
+        }
+        let mut data = sse::Data::new(event.data.as_ref());
+        let event_type = event.event.as_ref();
+        if !event_type.is_empty() && event_type != "message" {
+            data.set_event(event_type);
+        }
+        if !event.id.as_ref().is_empty() {
+            data.set_id(event.id.as_ref());
+        }
+        Ok(sse::Event::Data(data))
+    }
+}
+
+/// Convert actix-web-lab's event type, parsing the encoding of data messages
+impl TryFrom<sse::Event> for Frame {
+    type Error = NativeEventError;
+
+    fn try_from(event: sse::Event) -> Result<Self, NativeEventError> {
+        if let sse::Event::Comment(comment) = event {
+            return comment_frame(&comment);
+        }
+        let mut body = sse::Sse::from_stream(Once(Some(Ok::<_, Infallible>(event))));
+        parse_frame(&render(|cx| {
+            MessageBody::poll_next(Pin::new(&mut body), cx)
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncodeEventsource;
+    use actix_web::test::TestRequest;
+    use futures::prelude::*;
+
+    #[tokio::test]
+    async fn responder() {
+        let events = vec![Event {
+            data: "first".to_string(),
+            ..Default::default()
+        }];
+        let request = TestRequest::default().to_http_request();
+        let response = stream::iter(events)
+            .encode_eventsource()
+            .respond_to(&request);
+        let headers = response.headers();
+        assert_eq!(headers.get("content-type").unwrap(), "text/event-stream");
+        assert_eq!(headers.get("x-accel-buffering").unwrap(), "no");
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(body, "data: first\n\n");
+    }
+
+    #[test]
+    fn conversions() {
+        let event = Event {
+            event: "update".to_string(),
+            data: "line 1\r\nline 2".to_string(),
+            id: "7".to_string(),
+            ..Default::default()
+        };
+        let native = sse::Event::try_from(event.clone()).unwrap();
+        let expected = Event {
+            data: "line 1\nline 2".to_string(),
+            ..event
+        };
+        assert_eq!(Frame::try_from(native), Ok(Frame::Event(expected)));
+
+        let invalid = Event::<String> {
+            event: "a\nb".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            sse::Event::try_from(invalid).unwrap_err(),
+            EncodeError::InvalidEventType
+        );
+
+        let retry = Event::<String> {
+            retry: Some(core::time::Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert_eq!(
+            sse::Event::try_from(retry).unwrap_err(),
+            EncodeError::UnsupportedField
+        );
+
+        let comment = sse::Event::Comment("ping".into());
+        assert_eq!(
+            Frame::try_from(comment),
+            Ok(Frame::Comment(" ping".to_string()))
+        );
+        let comment = sse::Event::Comment("first\r\nsecond".into());
+        assert_eq!(
+            Frame::try_from(comment),
+            Err(NativeEventError::MultipleFrames)
+        );
+        let invalid = sse::Event::Data(sse::Data::new("payload").id("1\n2"));
+        assert_eq!(Frame::try_from(invalid), Err(NativeEventError::Unencoded));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::event::{Event, Frame};
+use crate::event_parser::EventParser;
+use crate::event_stream::{EventStream, EventStreamError, FrameStream};
+use core::pin::Pin;
+use futures_core::ready;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use futures_io::AsyncBufRead;
+use pin_project_lite::pin_project;
+use std::io;
+
+pin_project! {
+/// Source of an [`EventStream`] reading from a [`futures_io::AsyncBufRead`], created with
+/// [`EventStream::from_reader`]
+#[derive(Debug)]
+pub struct AsyncBufReadSource<R> {
+    #[pin]
+    reader: R,
+}
+}
+
+impl<R> AsyncBufReadSource<R> {
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwrap the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead> EventStream<AsyncBufReadSource<R>> {
+    /// Initialize the EventStream with an `AsyncBufRead`, such as a file or socket wrapped in a
+    /// `futures::io::BufReader`
+    ///
+    /// The bytes are copied straight from the reader's buffer into the parser, without
+    /// allocating a chunk for every read. Errors of the reader are returned as
+    /// [`EventStreamError::Transport`].
+    ///
+    /// ```
+    /// use eventsource_stream::EventStream;
+    /// use futures::prelude::*;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let reader = futures::io::Cursor::new(b"data: Hello, world!\n\n");
+    /// let mut events = EventStream::from_reader(reader);
+    /// let event = events.next().await.unwrap().unwrap();
+    /// assert_eq!(event.data, "Hello, world!");
+    /// # });
+    /// ```
+    pub fn from_reader(reader: R) -> Self {
+        EventStream::new(AsyncBufReadSource { reader })
+    }
+}
+
+/// Feed the parser with the buffered bytes of the reader
+fn fill<R: AsyncBufRead>(
+    source: Pin<&mut AsyncBufReadSource<R>>,
+    parser: &mut EventParser,
+    cx: &mut Context,
+) -> Poll<Option<io::Result<()>>> {
+    let mut reader = source.project().reader;
+    let len = match ready!(reader.as_mut().poll_fill_buf(cx)) {
+        Ok([]) => return Poll::Ready(None),
+        Ok(buf) => {
+            parser.feed(buf);
+            buf.len()
+        }
+        Err(err) => return Poll::Ready(Some(Err(err))),
+    };
+    reader.consume(len);
+    Poll::Ready(Some(Ok(())))
+}
+
+impl<R: AsyncBufRead> Stream for EventStream<AsyncBufReadSource<R>> {
+    type Item = Result<Event, EventStreamError<io::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_parser(cx, fill, EventParser::next_event)
+    }
+}
+
+impl<R: AsyncBufRead> Stream for FrameStream<AsyncBufReadSource<R>> {
+    type Item = Result<Frame, EventStreamError<io::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner().poll_parser(cx, fill, EventParser::next_frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{Trickle, INPUT};
+    use futures::io::BufReader;
+    use futures::prelude::*;
+
+    #[test]
+    fn from_reader() {
+        let reader = BufReader::new(Trickle {
+            input: INPUT,
+            errors: vec![io::ErrorKind::BrokenPipe],
+        });
+        let results = futures::executor::block_on(
+            EventStream::from_reader(reader)
+                .with_lossy_utf8()
+                .frames()
+                .collect::<Vec<_>>(),
+        );
+        let frames = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .collect::<Vec<_>>();
+        assert!(matches!(frames[0], Frame::Event(event) if event.data == "first"));
+        assert!(matches!(frames[1], Frame::Event(event) if event.data == "\u{FFFD}"));
+        assert!(matches!(frames[2], Frame::Comment(comment) if comment == " comment"));
+        assert!(matches!(frames[3], Frame::Event(event) if event.data.len() == 16));
+        assert!(
+            matches!(frames[4], Frame::Event(event) if event.data == "second" && event.id == "1")
+        );
+        assert_eq!(frames.len(), 5);
+        assert_eq!(
+            results
+                .iter()
+                .filter(|result| matches!(result, Err(EventStreamError::Transport(_))))
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::encode_stream::EncodeStream;
+use crate::encoder::{check, lines, EncodeError};
+use crate::event::{Event, Frame};
+use crate::framework::{parse_frame, render, NativeEventError, Once};
+use crate::response::event_stream_response;
+use crate::timer::Timer;
+use axum::body::Body;
+use axum::response::{sse, IntoResponse, Response};
+use core::convert::{Infallible, TryFrom};
+use core::pin::Pin;
+use futures_core::stream::Stream;
+use http_body::Body as _;
+
+/// Respond with the encoded events, with the headers set by [`crate::event_stream_response`]
+///
+/// ```
+/// use axum::response::IntoResponse;
+/// use eventsource_stream::{EncodeEventsource, Event};
+///
+/// async fn handler() -> impl IntoResponse {
+///     futures::stream::iter(vec![Event {
+///         data: "Hello, world!".to_string(),
+///         ..Default::default()
+///     }])
+///     .encode_eventsource()
+/// }
+/// ```
+impl<S, T, Tm> IntoResponse for EncodeStream<S, Tm>
+where
+    S: Stream<Item = Event<T>> + Send + 'static,
+    T: AsRef<str>,
+    Tm: Timer + Send + 'static,
+    Tm::Sleep: Send,
+{
+    fn into_response(self) -> Response {
+        event_stream_response(Body::new(self))
+    }
+}
+
+/// Convert an event into axum's event type. Events with extension fields fail with
+/// [`EncodeError::UnsupportedField`], as axum's events can't carry them.
+impl<T: AsRef<str>> TryFrom<Event<T>> for sse::Event {
+    type Error = EncodeError;
+
+    fn try_from(event: Event<T>) -> Result<Self, EncodeError> {
+        check(&event)?;
+        if !event.extensions.is_empty() {
+            return Err(EncodeError::UnsupportedField);
+        }
+        let mut native = sse::Event::default();
+        let event_type = event.event.as_ref();
+        if !event_type.is_empty() && event_type != "message" {
+            native = native.event(event_type);
+        }
+        if !event.id.as_ref().is_empty() {
+            native = native.id(event.id.as_ref());
+        }
+        if let Some(retry) = event.retry {
+            native = native.retry(retry);
+        }
+        // axum splits CRLF into two lines, so line breaks are normalized to LF first
+        let data = lines(event.data.as_ref()).collect::<Vec<_>>().join("\n");
+        Ok(native.data(data))
+    }
+}
+
+/// Convert axum's event type by parsing its encoding
+impl TryFrom<sse::Event> for Frame {
+    type Error = NativeEventError;
+
+    fn try_from(event: sse::Event) -> Result<Self, NativeEventError> {
+        let events = Once(Some(Ok::<_, Infallible>(event)));
+        let mut body = sse::Sse::new(events).into_response().into_body();
+        let bytes = render(|cx| {
+            Pin::new(&mut body).poll_frame(cx).map(|frame| {
+                frame.map(|frame| frame.map(|frame| frame.into_data().unwrap_or_default()))
+            })
+        })?;
+        parse_frame(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncodeEventsource;
+    use core::time::Duration;
+    use futures::prelude::*;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn into_response() {
+        let events = vec![Event {
+            data: "first".to_string(),
+            ..Default::default()
+        }];
+        let response = IntoResponse::into_response(stream::iter(events).encode_eventsource());
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        assert_eq!(response.headers()["x-accel-buffering"], "no");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "data: first\n\n");
+    }
+
+    #[test]
+    fn conversions() {
+        let event = Event {
+            event: "update".to_string(),
+            data: "line 1\r\nline 2".to_string(),
+            id: "7".to_string(),
+            retry: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        let native = sse::Event::try_from(event.clone()).unwrap();
+        let expected = Event {
+            data: "line 1\nline 2".to_string(),
+            ..event
+        };
+        assert_eq!(Frame::try_from(native), Ok(Frame::Event(expected)));
+
+        let invalid = Event::<String> {
+            id: "1\n2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            sse::Event::try_from(invalid).unwrap_err(),
+            EncodeError::InvalidId
+        );
+
+        let mut extended = Event::default();
+        extended
+            .extensions
+            .push("channel".to_string(), "news".to_string());
+        assert_eq!(
+            sse::Event::try_from(extended).unwrap_err(),
+            EncodeError::UnsupportedField
+        );
+
+        let comment = sse::Event::default().comment("ping");
+        assert_eq!(
+            Frame::try_from(comment),
+            Ok(Frame::Comment(" ping".to_string()))
+        );
+        let retry = sse::Event::default().retry(Duration::from_millis(500));
+        assert_eq!(
+            Frame::try_from(retry),
+            Ok(Frame::Retry(Duration::from_millis(500)))
+        );
+        let both = sse::Event::default().comment("ping").data("first");
+        assert_eq!(Frame::try_from(both), Err(NativeEventError::MultipleFrames));
+    }
+}
@@ -0,0 +1,301 @@
+use core::time::Duration;
+
+/// How a reconnection time sent by the server in a `retry` field is combined with the delay of
+/// a [`BackoffPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerRetry {
+    /// Use the server's reconnection time instead of the policy's delay
+    Override,
+    /// Use the server's reconnection time as a lower bound for the policy's delay
+    Floor,
+    /// Ignore the server's reconnection time
+    Ignore,
+}
+
+impl ServerRetry {
+    fn apply(self, delay: Duration, retry: Option<Duration>) -> Duration {
+        match (self, retry) {
+            (Self::Override, Some(retry)) => retry,
+            (Self::Floor, Some(retry)) => delay.max(retry),
+            _ => delay,
+        }
+    }
+}
+
+/// Decides how long an [`crate::EventSource`] waits before reconnecting
+///
+/// Policies are given the number of the upcoming attempt rather than measuring time
+/// themselves. The healthy period is timed by the source's [`crate::Timer`], so a fake timer is
+/// enough to test a policy without waiting for real time to pass.
+pub trait BackoffPolicy {
+    /// Get the delay before reconnect `attempt`, counting from 1 since the last healthy
+    /// connection, given the last reconnection time sent by the server. Returning `None` gives
+    /// up and ends the source.
+    fn delay(&mut self, attempt: u32, retry: Option<Duration>) -> Option<Duration>;
+
+    /// How long a connection must stay open before the attempt count is reset. By default it
+    /// is reset as soon as a connection is established.
+    fn healthy_period(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Reconnect after a fixed delay, unless the server sent a reconnection time
+///
+/// This is the behaviour the HTML spec describes and the default of an
+/// [`crate::EventSource`], with a delay of 3 seconds.
+#[derive(Debug, Clone)]
+pub struct ConstantBackoff {
+    delay: Duration,
+    max_attempts: Option<u32>,
+    server_retry: ServerRetry,
+}
+
+impl ConstantBackoff {
+    /// Reconnect after `delay`
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            max_attempts: None,
+            server_retry: ServerRetry::Override,
+        }
+    }
+
+    /// Give up after `attempts` reconnects in a row
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Choose how the server's reconnection time is used. Defaults to [`ServerRetry::Override`].
+    pub fn server_retry(mut self, server_retry: ServerRetry) -> Self {
+        self.server_retry = server_retry;
+        self
+    }
+}
+
+impl Default for ConstantBackoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(3))
+    }
+}
+
+impl BackoffPolicy for ConstantBackoff {
+    fn delay(&mut self, attempt: u32, retry: Option<Duration>) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt > max) {
+            return None;
+        }
+        Some(self.server_retry.apply(self.delay, retry))
+    }
+}
+
+/// Reconnect after exponentially growing delays
+///
+/// The first reconnect waits `initial`, and every following one twice as long as the one
+/// before, up to `max`. Jitter randomly shortens each delay to keep many clients from
+/// reconnecting at the same time.
+///
+/// ```
+/// use eventsource_stream::{ExponentialBackoff, ServerRetry};
+/// use std::time::Duration;
+///
+/// let backoff = ExponentialBackoff::new(Duration::from_millis(500), Duration::from_secs(30))
+///     .jitter(0.5)
+///     .max_attempts(10)
+///     .reset_after(Duration::from_secs(60))
+///     .server_retry(ServerRetry::Floor);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    initial: Duration,
+    max: Duration,
+    jitter: f64,
+    max_attempts: Option<u32>,
+    reset_after: Option<Duration>,
+    server_retry: ServerRetry,
+    rng: u64,
+}
+
+impl ExponentialBackoff {
+    /// Start with a delay of `initial`, doubling up to at most `max`
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            jitter: 0.0,
+            max_attempts: None,
+            reset_after: None,
+            server_retry: ServerRetry::Floor,
+            rng: seed(),
+        }
+    }
+
+    /// Shorten each delay by a random fraction of up to `jitter`, which is clamped to `0..=1`
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Give up after `attempts` reconnects in a row
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Only start over at the initial delay once a connection stayed open for `period`
+    pub fn reset_after(mut self, period: Duration) -> Self {
+        self.reset_after = Some(period);
+        self
+    }
+
+    /// Choose how the server's reconnection time is used. Defaults to [`ServerRetry::Floor`].
+    pub fn server_retry(mut self, server_retry: ServerRetry) -> Self {
+        self.server_retry = server_retry;
+        self
+    }
+
+    /// Next pseudo random number in `0..1`, using SplitMix64
+    fn random(&mut self) -> f64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl BackoffPolicy for ExponentialBackoff {
+    fn delay(&mut self, attempt: u32, retry: Option<Duration>) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt > max) {
+            return None;
+        }
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        let mut delay = self.initial.saturating_mul(factor).min(self.max);
+        if self.jitter > 0.0 {
+            delay = delay.mul_f64(1.0 - self.jitter * self.random());
+        }
+        Some(self.server_retry.apply(delay, retry))
+    }
+
+    fn healthy_period(&self) -> Option<Duration> {
+        self.reset_after
+    }
+}
+
+/// Seed for the jitter of a new policy, which differs between policies where possible
+fn seed() -> u64 {
+    #[cfg(feature = "std")]
+    {
+        use std::hash::{BuildHasher, Hasher};
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0x853c_49e6_748f_ea9b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delays(policy: &mut impl BackoffPolicy, retry: Option<Duration>) -> Vec<Option<u64>> {
+        (1..=6)
+            .map(|attempt| {
+                policy
+                    .delay(attempt, retry)
+                    .map(|delay| delay.as_millis() as u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant() {
+        let mut policy = ConstantBackoff::default().max_attempts(4);
+        assert_eq!(
+            delays(&mut policy, None),
+            vec![Some(3000), Some(3000), Some(3000), Some(3000), None, None]
+        );
+        assert_eq!(
+            delays(&mut policy, Some(Duration::from_millis(100)))[0],
+            Some(100)
+        );
+        assert_eq!(policy.healthy_period(), None);
+    }
+
+    #[test]
+    fn exponential() {
+        let mut policy =
+            ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1))
+                .max_attempts(5)
+                .reset_after(Duration::from_secs(60));
+        assert_eq!(
+            delays(&mut policy, None),
+            vec![Some(100), Some(200), Some(400), Some(800), Some(1000), None]
+        );
+        assert_eq!(policy.healthy_period(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            policy.delay(u32::MAX - 1, None),
+            None,
+            "attempts beyond the limit give up"
+        );
+
+        let mut policy =
+            ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(policy.delay(40, None), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn server_retry() {
+        let retry = Some(Duration::from_millis(300));
+        let policy = ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(
+            delays(&mut policy.clone(), retry),
+            vec![
+                Some(300),
+                Some(300),
+                Some(400),
+                Some(800),
+                Some(1000),
+                Some(1000)
+            ]
+        );
+        assert_eq!(
+            delays(
+                &mut policy.clone().server_retry(ServerRetry::Override),
+                retry
+            ),
+            vec![Some(300); 6]
+        );
+        assert_eq!(
+            delays(&mut policy.server_retry(ServerRetry::Ignore), retry),
+            vec![
+                Some(100),
+                Some(200),
+                Some(400),
+                Some(800),
+                Some(1000),
+                Some(1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        let mut policy =
+            ExponentialBackoff::new(Duration::from_millis(1000), Duration::from_secs(1))
+                .jitter(0.5);
+        let delays = (1..100)
+            .map(|attempt| policy.delay(attempt, None).unwrap())
+            .collect::<Vec<_>>();
+        assert!(delays
+            .iter()
+            .all(|delay| *delay >= Duration::from_millis(500) && *delay <= Duration::from_secs(1)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+}
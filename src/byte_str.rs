@@ -0,0 +1,230 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use crate::text::private::{Line, Sealed};
+use crate::text::EventText;
+use bytes::Bytes;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use core::str::Utf8Error;
+
+/// An immutable, reference counted UTF8 string backed by [`Bytes`]
+///
+/// Used as the field type of a zero-copy [`crate::Event<ByteStr>`], so that fields can share
+/// the memory of the chunk they were parsed from.
+#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteStr {
+    bytes: Bytes,
+}
+
+impl ByteStr {
+    /// Create a new empty string
+    pub const fn new() -> Self {
+        Self {
+            bytes: Bytes::new(),
+        }
+    }
+
+    /// Create a string from a static str without copying it
+    pub const fn from_static(value: &'static str) -> Self {
+        Self {
+            bytes: Bytes::from_static(value.as_bytes()),
+        }
+    }
+
+    /// Create a string from bytes which are valid UTF8
+    pub fn from_utf8(bytes: Bytes) -> Result<Self, Utf8Error> {
+        core::str::from_utf8(&bytes)?;
+        Ok(Self { bytes })
+    }
+
+    /// Get the string as a str
+    pub fn as_str(&self) -> &str {
+        // SAFETY: `bytes` is only ever set from valid UTF8
+        unsafe { core::str::from_utf8_unchecked(&self.bytes) }
+    }
+
+    /// Get the underlying bytes
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// Convert the string into the underlying bytes
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl EventText for ByteStr {}
+
+impl Sealed for ByteStr {
+    fn from_field(line: &Line, value: &str) -> Self {
+        let bytes = match line.shared {
+            Some(chunk) => chunk.slice_ref(value.as_bytes()),
+            None => Bytes::copy_from_slice(value.as_bytes()),
+        };
+        Self { bytes }
+    }
+
+    fn from_static(value: &'static str) -> Self {
+        Self::from_static(value)
+    }
+}
+
+impl Deref for ByteStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ByteStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for ByteStr {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Borrow<str> for ByteStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<String> for ByteStr {
+    fn from(value: String) -> Self {
+        Self {
+            bytes: Bytes::from(value),
+        }
+    }
+}
+
+impl From<&str> for ByteStr {
+    fn from(value: &str) -> Self {
+        Self {
+            bytes: Bytes::copy_from_slice(value.as_bytes()),
+        }
+    }
+}
+
+impl From<ByteStr> for Bytes {
+    fn from(value: ByteStr) -> Self {
+        value.bytes
+    }
+}
+
+impl PartialEq<str> for ByteStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ByteStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for ByteStr {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for ByteStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ByteStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, EventParser, SharedEventsource};
+    use futures::prelude::*;
+
+    fn is_shared(chunk: &Bytes, value: &ByteStr) -> bool {
+        !value.is_empty() && chunk.as_ptr_range().contains(&value.as_ptr())
+    }
+
+    #[test]
+    fn shares_single_line_fields() {
+        let chunk = Bytes::from_static(b"event: add\nid: 7\ndata: shared\n\ndata: a\ndata: b\n\n");
+        let mut parser = EventParser::<ByteStr>::default();
+        parser.feed_bytes(chunk.clone());
+
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.event, "add");
+        assert_eq!(event.id, "7");
+        assert_eq!(event.data, "shared");
+        assert!(is_shared(&chunk, &event.event));
+        assert!(is_shared(&chunk, &event.id));
+        assert!(is_shared(&chunk, &event.data));
+
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data, "a\nb");
+        assert!(!is_shared(&chunk, &event.data));
+        assert_eq!(parser.last_event_id(), "7");
+    }
+
+    #[test]
+    fn copies_lines_split_across_chunks() {
+        let chunks = [
+            Bytes::from_static(b"data: hel"),
+            Bytes::from_static(b"lo\n\n"),
+            Bytes::from_static(b"data: again\n\n"),
+        ];
+        let mut parser = EventParser::<ByteStr>::default();
+        parser.feed_bytes(chunks[0].clone());
+        assert_eq!(parser.next_event().unwrap(), None);
+        parser.feed_bytes(chunks[1].clone());
+
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data, "hello");
+        assert!(!chunks.iter().any(|chunk| is_shared(chunk, &event.data)));
+
+        parser.feed_bytes(chunks[2].clone());
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data, "again");
+        assert!(is_shared(&chunks[2], &event.data));
+    }
+
+    #[tokio::test]
+    async fn shared_event_stream() {
+        assert_eq!(
+            futures::stream::iter(vec![
+                Ok::<_, ()>(Bytes::from_static(b"data: Hello,\n\nevent: greeting\n")),
+                Ok::<_, ()>(Bytes::from_static(b"data: world!\n\n")),
+            ])
+            .eventsource_shared()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap(),
+            vec![
+                Event {
+                    event: ByteStr::from_static("message"),
+                    data: ByteStr::from_static("Hello,"),
+                    ..Default::default()
+                },
+                Event {
+                    event: ByteStr::from_static("greeting"),
+                    data: ByteStr::from_static("world!"),
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::encoder::{EncodeError, EventEncoder};
+use crate::event::{Event, Frame};
+use crate::event_parser::{EventParser, EventParserError};
+use crate::limits::Limits;
+use crate::text::EventText;
+use bytes::{Bytes, BytesMut};
+use memchr::memrchr2;
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// A [`Decoder`] and [`Encoder`] for the `text/event-stream` format
+///
+/// Lets [`tokio_util::codec::FramedRead`] parse events straight out of an `AsyncRead`, and
+/// [`tokio_util::codec::FramedWrite`] write events into an `AsyncWrite`. Decoding follows the
+/// same rules as an [`crate::EventStream`], and encoding those of an [`EventEncoder`].
+///
+/// Errors of the stream contents are decoded as items, so reading continues with the following
+/// events, while IO errors end the stream. Only complete lines are taken out of the read buffer,
+/// so the bytes of a partial line wait there until its end-of-line is read.
+///
+/// ```
+/// use eventsource_stream::EventCodec;
+/// use futures::prelude::*;
+/// use tokio_util::codec::FramedRead;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let input: &[u8] = b"data: Hello,\ndata: world!\n\n";
+/// let mut events = FramedRead::new(input, EventCodec::new());
+/// let event = events.next().await.unwrap().unwrap().unwrap();
+/// assert_eq!(event.data, "Hello,\nworld!");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EventCodec<T = String> {
+    parser: EventParser<T>,
+    encoder: EventEncoder,
+    limits: Limits,
+    /// Bytes at the start of the read buffer which are known to contain no end-of-line
+    scanned: usize,
+}
+
+impl EventCodec {
+    /// Create a codec for a new stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: EventText> Default for EventCodec<T> {
+    fn default() -> Self {
+        Self {
+            parser: EventParser::default(),
+            encoder: EventEncoder::new(),
+            limits: Limits::default(),
+            scanned: 0,
+        }
+    }
+}
+
+impl<T: EventText> EventCodec<T> {
+    /// Collect fields other than `event`, `data`, `id` and `retry` into
+    /// [`Event::extensions`] instead of ignoring them
+    pub fn with_extensions(mut self) -> Self {
+        self.parser = self.parser.with_extensions();
+        self
+    }
+
+    /// Decode invalid UTF8 sequences to U+FFFD REPLACEMENT CHARACTER instead of failing
+    pub fn with_lossy_utf8(mut self) -> Self {
+        self.parser = self.parser.with_lossy_utf8();
+        self
+    }
+
+    /// Enforce memory limits while decoding
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.parser = self.parser.with_limits(limits);
+        self.limits = limits;
+        self
+    }
+
+    /// Use `encoder` to encode the events, for example one created with
+    /// [`EventEncoder::with_last_event_id`] when resuming a stream
+    pub fn with_encoder(mut self, encoder: EventEncoder) -> Self {
+        self.encoder = encoder;
+        self
+    }
+
+    /// Set the last event ID of the decoded stream
+    pub fn set_last_event_id(&mut self, id: impl Into<String>) {
+        self.parser.set_last_event_id(id);
+    }
+
+    /// Get the ID of the last decoded event
+    pub fn last_event_id(&self) -> &str {
+        self.parser.last_event_id()
+    }
+}
+
+impl<T: EventText> EventCodec<T> {
+    /// Split the complete lines off the front of the read buffer. A partial line is only
+    /// taken once it exceeds the limits, so that the parser drops it.
+    fn split_lines(&mut self, src: &mut BytesMut) -> Option<Bytes> {
+        match memrchr2(b'\r', b'\n', &src[self.scanned..]) {
+            Some(end) => {
+                let lines = src.split_to(self.scanned + end + 1).freeze();
+                self.scanned = 0;
+                Some(lines)
+            }
+            None => {
+                self.scanned = src.len();
+                let limits = &self.limits;
+                let max = limits
+                    .max_line_length
+                    .into_iter()
+                    .chain(limits.max_buffer_size)
+                    .min()?;
+                (src.len() > max).then(|| {
+                    self.scanned = 0;
+                    src.split().freeze()
+                })
+            }
+        }
+    }
+
+    fn next_item(&mut self) -> Option<Result<Event<T>, EventParserError>> {
+        self.parser.next_event().transpose()
+    }
+}
+
+impl<T: EventText> Decoder for EventCodec<T> {
+    type Item = Result<Event<T>, EventParserError>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        if let Some(item) = self.next_item() {
+            return Ok(Some(item));
+        }
+        // The parser holds no partial line here, so it takes the lines without copying them
+        match self.split_lines(src) {
+            Some(lines) => {
+                self.parser.feed_bytes(lines);
+                Ok(self.next_item())
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        if let Some(item) = self.decode(src)? {
+            return Ok(Some(item));
+        }
+        if !src.is_empty() {
+            self.scanned = 0;
+            self.parser.feed_bytes(src.split().freeze());
+        }
+        if !self.parser.is_finished() {
+            if let Err(err) = self.parser.finish() {
+                return Ok(Some(Err(err)));
+            }
+        }
+        Ok(self.next_item())
+    }
+}
+
+fn encode_error(err: EncodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+impl<T: EventText, U: AsRef<str>> Encoder<Event<U>> for EventCodec<T> {
+    type Error = io::Error;
+
+    fn encode(&mut self, event: Event<U>, dst: &mut BytesMut) -> io::Result<()> {
+        let mut buf = String::new();
+        self.encoder
+            .encode_into(&event, &mut buf)
+            .map_err(encode_error)?;
+        dst.extend_from_slice(buf.as_bytes());
+        Ok(())
+    }
+}
+
+impl<T: EventText, U: AsRef<str>> Encoder<Frame<U>> for EventCodec<T> {
+    type Error = io::Error;
+
+    fn encode(&mut self, frame: Frame<U>, dst: &mut BytesMut) -> io::Result<()> {
+        let mut buf = String::new();
+        self.encoder
+            .encode_frame_into(&frame, &mut buf)
+            .map_err(encode_error)?;
+        dst.extend_from_slice(buf.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::message;
+    use crate::ByteStr;
+    use futures::prelude::*;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    #[tokio::test]
+    async fn decode() {
+        let input: &[u8] =
+            b"\xEF\xBB\xBFid: 1\ndata: first\r\n\r\n: comment\n\ndata: second\n\ndata: trailing";
+        let events = FramedRead::with_capacity(input, EventCodec::new(), 4)
+            .map(Result::unwrap)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(events, vec![message("first", "1"), message("second", "1")]);
+
+        let input: &[u8] = b"data: shared\n\n";
+        let events = FramedRead::new(input, EventCodec::<ByteStr>::default())
+            .map(Result::unwrap)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(events[0].data, "shared");
+    }
+
+    #[tokio::test]
+    async fn decode_errors() {
+        let input: &[u8] = b"data: \xFF\n\ndata: ok\n\n";
+        let results = FramedRead::new(input, EventCodec::new())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(results[0], Err(EventParserError::Parser(_))));
+        assert_eq!(results[1].as_ref().unwrap().data, "ok");
+        assert_eq!(results.len(), 2);
+
+        let events = FramedRead::new(input, EventCodec::new().with_lossy_utf8())
+            .map(Result::unwrap)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(events[0].data, "\u{FFFD}");
+        assert_eq!(events[1].data, "ok");
+
+        // A partial line is dropped once it exceeds the limits, before the read buffer grows
+        let input: &[u8] = b"data: 0123456789abcdef\n\ndata: ok\n\n";
+        let codec = EventCodec::new().with_limits(Limits::new().max_line_length(10));
+        let mut results = FramedRead::with_capacity(input, codec, 4).map(Result::unwrap);
+        assert!(matches!(
+            results.next().await,
+            Some(Err(EventParserError::LineTooLong(10)))
+        ));
+        assert_eq!(results.get_ref().read_buffer().len(), 0);
+        assert_eq!(results.next().await.unwrap().unwrap().data, "ok");
+        assert!(results.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let events = vec![message("first", "1"), message("second\nline", "1")];
+        let mut output = FramedWrite::new(Vec::new(), EventCodec::new());
+        output
+            .send(Frame::<String>::Retry(core::time::Duration::from_secs(1)))
+            .await
+            .unwrap();
+        for event in events.clone() {
+            output.send(event).await.unwrap();
+        }
+        output.send(Frame::Comment("keep-alive")).await.unwrap();
+        let output = output.into_inner();
+        assert_eq!(
+            output,
+            b"retry: 1000\n\nid: 1\ndata: first\n\ndata: second\ndata: line\n\n:keep-alive\n"
+        );
+
+        let decoded = FramedRead::new(&output[..], EventCodec::new())
+            .map(Result::unwrap)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(decoded, events);
+
+        let mut output = FramedWrite::new(Vec::new(), EventCodec::new());
+        let err = output
+            .send(Event {
+                id: "a\nb".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(output.into_inner().is_empty());
+    }
+}
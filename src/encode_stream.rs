@@ -0,0 +1,331 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use crate::encoder::{EncodeError, EventEncoder};
+use crate::event::{Event, Frame};
+use crate::timer::{NoTimer, Timer};
+use bytes::Bytes;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use pin_project_lite::pin_project;
+
+/// Keep-alive comments sent by an [`EncodeStream`] while no events are sent
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    interval: Duration,
+    comment: String,
+}
+
+impl KeepAlive {
+    /// Send an empty comment whenever no event has been sent for `interval`
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            comment: String::new(),
+        }
+    }
+
+    /// Set the text of the keep-alive comment
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+}
+
+pin_project! {
+/// A Stream of encoded event stream bytes, suitable as the body of an HTTP response
+///
+/// Created from a stream of events with [`crate::EncodeEventsource::encode_eventsource`].
+/// Keep-alive comments are only sent when configured with [`EncodeStream::with_keep_alive`],
+/// using a [`Timer`] of the caller's runtime.
+///
+/// ```
+/// use eventsource_stream::{EncodeEventsource, Event, KeepAlive};
+/// use std::time::Duration;
+///
+/// let body = futures::stream::iter(vec![Event {
+///     data: "Hello, world!".to_string(),
+///     ..Default::default()
+/// }])
+/// .encode_eventsource()
+/// .with_retry(Duration::from_secs(5))
+/// .with_keep_alive(KeepAlive::new(Duration::from_secs(15)), |duration: Duration| async move {
+///     // tokio::time::sleep(duration).await
+/// });
+/// ```
+pub struct EncodeStream<S, Tm: Timer> {
+    #[pin]
+    stream: S,
+    encoder: EventEncoder,
+    retry: Option<Duration>,
+    keep_alive: Option<KeepAlive>,
+    timer: Tm,
+    #[pin]
+    sleep: Option<Tm::Sleep>,
+    is_terminated: bool,
+}
+}
+
+impl<S> EncodeStream<S, NoTimer> {
+    /// Initialize the EncodeStream with a Stream of events
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            encoder: EventEncoder::new(),
+            retry: None,
+            keep_alive: None,
+            timer: NoTimer,
+            sleep: None,
+            is_terminated: false,
+        }
+    }
+
+    /// Send a keep-alive comment whenever no event has been sent within its interval, timed by
+    /// `timer`
+    pub fn with_keep_alive<Tm: Timer>(
+        self,
+        keep_alive: KeepAlive,
+        timer: Tm,
+    ) -> EncodeStream<S, Tm> {
+        EncodeStream {
+            stream: self.stream,
+            encoder: self.encoder,
+            retry: self.retry,
+            keep_alive: Some(keep_alive),
+            timer,
+            sleep: None,
+            is_terminated: self.is_terminated,
+        }
+    }
+}
+
+impl<S, Tm: Timer> EncodeStream<S, Tm> {
+    /// Send a `retry` field with the given reconnection time before the first event
+    pub fn with_retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Use `encoder` to encode the events, for example one created with
+    /// [`EventEncoder::with_last_event_id`] when resuming a stream
+    pub fn with_encoder(mut self, encoder: EventEncoder) -> Self {
+        self.encoder = encoder;
+        self
+    }
+
+    /// Wrap this into an `http::Response` with the headers of an event stream, see
+    /// [`crate::event_stream_response`]
+    ///
+    /// ```
+    /// use eventsource_stream::{EncodeEventsource, Event};
+    ///
+    /// let response = futures::stream::iter(vec![Event::<String>::default()])
+    ///     .encode_eventsource()
+    ///     .into_response();
+    /// assert_eq!(response.headers()["content-type"], "text/event-stream");
+    /// ```
+    #[cfg(feature = "hyper")]
+    pub fn into_response(self) -> http::Response<Self> {
+        crate::response::event_stream_response(self)
+    }
+}
+
+impl<S, T, Tm> Stream for EncodeStream<S, Tm>
+where
+    S: Stream<Item = Event<T>>,
+    T: AsRef<str>,
+    Tm: Timer,
+{
+    type Item = Result<Bytes, EncodeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if *this.is_terminated {
+            return Poll::Ready(None);
+        }
+
+        let mut buf = String::new();
+        if let Some(retry) = this.retry.take() {
+            let _ = this
+                .encoder
+                .encode_frame_into(&Frame::<String>::Retry(retry), &mut buf);
+            return Poll::Ready(Some(Ok(buf.into())));
+        }
+
+        match this.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(event)) => {
+                this.sleep.set(None);
+                let result = this.encoder.encode_into(&event, &mut buf);
+                return Poll::Ready(Some(result.map(|_| buf.into())));
+            }
+            Poll::Ready(None) => {
+                *this.is_terminated = true;
+                return Poll::Ready(None);
+            }
+            Poll::Pending => {}
+        }
+
+        let keep_alive = match this.keep_alive {
+            Some(keep_alive) => keep_alive,
+            None => return Poll::Pending,
+        };
+        loop {
+            match this.sleep.as_mut().as_pin_mut() {
+                Some(sleep) => match sleep.poll(cx) {
+                    Poll::Ready(()) => {
+                        this.sleep.set(None);
+                        let comment = Frame::Comment(keep_alive.comment.as_str());
+                        let _ = this.encoder.encode_frame_into(&comment, &mut buf);
+                        buf.push('\n');
+                        return Poll::Ready(Some(Ok(buf.into())));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => this.sleep.set(Some(this.timer.sleep(keep_alive.interval))),
+            }
+        }
+    }
+}
+
+/// Every event, keep-alive and retry hint is sent as a separate data frame, so that it is
+/// flushed to the client right away. Events which can't be encoded fail the body with an
+/// [`EncodeError`].
+#[cfg(feature = "hyper")]
+impl<S, T, Tm> http_body::Body for EncodeStream<S, Tm>
+where
+    S: Stream<Item = Event<T>>,
+    T: AsRef<str>,
+    Tm: Timer,
+{
+    type Data = Bytes;
+    type Error = EncodeError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<http_body::Frame<Bytes>, EncodeError>>> {
+        self.poll_next(cx)
+            .map(|item| item.map(|result| result.map(http_body::Frame::data)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.is_terminated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::message;
+    use crate::{EncodeEventsource, Eventsource};
+    use futures::prelude::*;
+
+    #[tokio::test]
+    async fn encode_events() {
+        let events = vec![message("first", ""), message("second\nline", "")];
+        let chunks = stream::iter(events.clone())
+            .encode_eventsource()
+            .with_retry(Duration::from_millis(500))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                Bytes::from_static(b"retry: 500\n\n"),
+                Bytes::from_static(b"data: first\n\n"),
+                Bytes::from_static(b"data: second\ndata: line\n\n"),
+            ]
+        );
+
+        let parsed = stream::iter(chunks.into_iter().map(Ok::<_, ()>))
+            .eventsource()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(parsed, events);
+    }
+
+    #[tokio::test]
+    async fn keep_alive() {
+        let keep_alive = KeepAlive::new(Duration::from_secs(15)).comment("ping");
+        let chunks = stream::iter(vec![message("first", "")])
+            .chain(stream::pending())
+            .encode_eventsource()
+            .with_keep_alive(keep_alive, |interval| {
+                assert_eq!(interval, Duration::from_secs(15));
+                future::ready(())
+            })
+            .take(3)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                Bytes::from_static(b"data: first\n\n"),
+                Bytes::from_static(b":ping\n\n"),
+                Bytes::from_static(b":ping\n\n"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn body() {
+        use http_body::Body;
+        use http_body_util::BodyExt;
+
+        let response = stream::iter(vec![message("first", ""), message("second", "")])
+            .encode_eventsource()
+            .with_retry(Duration::from_millis(500))
+            .into_response();
+        assert_eq!(response.headers()["cache-control"], "no-cache");
+
+        let mut body = response.into_body();
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(
+            frames,
+            vec![
+                Bytes::from_static(b"retry: 500\n\n"),
+                Bytes::from_static(b"data: first\n\n"),
+                Bytes::from_static(b"data: second\n\n"),
+            ]
+        );
+        assert!(body.is_end_stream());
+
+        let mut body = stream::iter(vec![Event {
+            event: "a\nb".to_string(),
+            ..Default::default()
+        }])
+        .encode_eventsource();
+        assert_eq!(
+            body.frame().await.unwrap().unwrap_err(),
+            EncodeError::InvalidEventType
+        );
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn round_trip() {
+        let events = vec![message("first", ""), message("second\nline", "")];
+        let response = stream::iter(events.clone())
+            .encode_eventsource()
+            .into_response();
+        let parsed = crate::response_events(response)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].data, events[1].data);
+    }
+}
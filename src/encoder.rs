@@ -0,0 +1,315 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+use crate::event::{Event, Frame};
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+/// Error for an event which can't be represented in the event stream format
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The event type contains a line break
+    InvalidEventType,
+    /// The id contains a line break or U+0000 NULL
+    InvalidId,
+    /// An extension field name is empty, is one of the standard fields or contains a colon or
+    /// line break, or its value contains a line break
+    InvalidExtension,
+    /// The target can't carry a field of the event, such as the reconnection time or an
+    /// extension field
+    UnsupportedField,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEventType => f.write_str("event type contains a line break"),
+            Self::InvalidId => f.write_str("id contains a line break or NULL"),
+            Self::InvalidExtension => f.write_str("invalid extension field"),
+            Self::UnsupportedField => f.write_str("field can't be converted"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+fn has_line_break(value: &str) -> bool {
+    value.contains(['\r', '\n'])
+}
+
+/// Serializes events into the `text/event-stream` format
+///
+/// Data is split on CR, LF and CRLF into one `data` line each, the default `message` type is
+/// left out and an `id` line is only written when the id differs from that of the previous
+/// event, since the receiver carries ids over to the events which follow. An encoder should
+/// therefore be used for a single stream only. Everything it writes is parsed back into the
+/// same events by an [`crate::EventStream`].
+///
+/// ```
+/// use eventsource_stream::{Event, EventEncoder};
+///
+/// let mut encoder = EventEncoder::new();
+/// let event = Event {
+///     event: "update".to_string(),
+///     data: "line 1\nline 2".to_string(),
+///     id: "1".to_string(),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     encoder.encode(&event).unwrap(),
+///     "event: update\nid: 1\ndata: line 1\ndata: line 2\n\n"
+/// );
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct EventEncoder {
+    last_event_id: String,
+}
+
+impl EventEncoder {
+    /// Create an encoder for a new stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an encoder for a stream whose receiver already knows `id` as its last event ID,
+    /// for example when resuming after a `Last-Event-ID` header
+    pub fn with_last_event_id(id: impl Into<String>) -> Self {
+        Self {
+            last_event_id: id.into(),
+        }
+    }
+
+    /// Get the ID of the last encoded event
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    /// Encode an event into a new string
+    pub fn encode<T: AsRef<str>>(&mut self, event: &Event<T>) -> Result<String, EncodeError> {
+        let mut buf = String::new();
+        self.encode_into(event, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Append an encoded event to `buf`. Nothing is written if the event is rejected.
+    pub fn encode_into<T: AsRef<str>>(
+        &mut self,
+        event: &Event<T>,
+        buf: &mut String,
+    ) -> Result<(), EncodeError> {
+        check(event)?;
+        let event_type = event.event.as_ref();
+        let id = event.id.as_ref();
+
+        if !event_type.is_empty() && event_type != "message" {
+            write_field(buf, "event", event_type);
+        }
+        if id != self.last_event_id {
+            write_field(buf, "id", id);
+            self.last_event_id = id.to_string();
+        }
+        if let Some(retry) = event.retry {
+            write_retry(buf, retry);
+        }
+        for (name, value) in event.extensions.iter() {
+            write_field(buf, name.as_ref(), value.as_ref());
+        }
+        for line in lines(event.data.as_ref()) {
+            write_field(buf, "data", line);
+        }
+        buf.push('\n');
+        Ok(())
+    }
+
+    /// Append an encoded frame to `buf`. A comment containing line breaks is written as several
+    /// comment lines.
+    pub fn encode_frame_into<T: AsRef<str>>(
+        &mut self,
+        frame: &Frame<T>,
+        buf: &mut String,
+    ) -> Result<(), EncodeError> {
+        match frame {
+            Frame::Event(event) => return self.encode_into(event, buf),
+            Frame::Comment(comment) => {
+                for line in lines(comment.as_ref()) {
+                    buf.push(':');
+                    buf.push_str(line);
+                    buf.push('\n');
+                }
+            }
+            Frame::Retry(retry) => {
+                write_retry(buf, *retry);
+                buf.push('\n');
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that an event can be represented in the event stream format
+pub(crate) fn check<T: AsRef<str>>(event: &Event<T>) -> Result<(), EncodeError> {
+    let id = event.id.as_ref();
+    if has_line_break(event.event.as_ref()) {
+        return Err(EncodeError::InvalidEventType);
+    }
+    if has_line_break(id) || id.contains('\u{0000}') {
+        return Err(EncodeError::InvalidId);
+    }
+    for (name, value) in event.extensions.iter() {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        if name.is_empty()
+            || matches!(name, "event" | "data" | "id" | "retry")
+            || name.contains(':')
+            || has_line_break(name)
+            || has_line_break(value)
+        {
+            return Err(EncodeError::InvalidExtension);
+        }
+    }
+    Ok(())
+}
+
+/// Split `value` on CR, LF and CRLF
+pub(crate) fn lines(value: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(value);
+    core::iter::from_fn(move || {
+        let value = rest?;
+        match value.find(['\r', '\n']) {
+            Some(end) => {
+                let skip = if value[end..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                rest = Some(&value[end + skip..]);
+                Some(&value[..end])
+            }
+            None => {
+                rest = None;
+                Some(value)
+            }
+        }
+    })
+}
+
+fn write_field(buf: &mut String, name: &str, value: &str) {
+    buf.push_str(name);
+    if !value.is_empty() {
+        buf.push_str(": ");
+        buf.push_str(value);
+    } else {
+        buf.push(':');
+    }
+    buf.push('\n');
+}
+
+fn write_retry(buf: &mut String, retry: Duration) {
+    // Writing to a String can't fail
+    let _ = writeln!(buf, "retry: {}", retry.as_millis());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::event;
+    use crate::{EventParser, Extensions};
+
+    #[test]
+    fn fields() {
+        let mut encoder = EventEncoder::new();
+        assert_eq!(
+            encoder.encode(&event("message", "", "")).unwrap(),
+            "data:\n\n"
+        );
+        assert_eq!(
+            encoder.encode(&event("", "a\r\nb\rc\n", "1")).unwrap(),
+            "id: 1\ndata: a\ndata: b\ndata: c\ndata:\n\n"
+        );
+        assert_eq!(
+            encoder.encode(&event("", " spaced", "1")).unwrap(),
+            "data:  spaced\n\n"
+        );
+        let mut retry = event("ping", "", "");
+        retry.retry = Some(Duration::from_millis(1500));
+        assert_eq!(
+            encoder.encode(&retry).unwrap(),
+            "event: ping\nid:\nretry: 1500\ndata:\n\n"
+        );
+        assert_eq!(encoder.last_event_id(), "");
+    }
+
+    #[test]
+    fn frames() {
+        let mut encoder = EventEncoder::new();
+        let mut buf = String::new();
+        let frames = [
+            Frame::Comment(" keep-alive".to_string()),
+            Frame::Comment("a\nb".to_string()),
+            Frame::Retry(Duration::from_secs(1)),
+        ];
+        for frame in &frames {
+            encoder.encode_frame_into(frame, &mut buf).unwrap();
+        }
+        assert_eq!(buf, ": keep-alive\n:a\n:b\nretry: 1000\n\n");
+    }
+
+    #[test]
+    fn invalid() {
+        let mut encoder = EventEncoder::new();
+        let mut buf = String::new();
+        assert_eq!(
+            encoder.encode_into(&event("a\nb", "", ""), &mut buf),
+            Err(EncodeError::InvalidEventType)
+        );
+        assert_eq!(
+            encoder.encode_into(&event("", "", "1\r"), &mut buf),
+            Err(EncodeError::InvalidId)
+        );
+        assert_eq!(
+            encoder.encode_into(&event("", "", "\u{0000}"), &mut buf),
+            Err(EncodeError::InvalidId)
+        );
+        for (name, value) in [("", "x"), ("data", "x"), ("a:b", "x"), ("a", "x\ny")] {
+            let mut invalid = event("", "", "");
+            invalid.extensions.push(name.to_string(), value.to_string());
+            assert_eq!(
+                encoder.encode_into(&invalid, &mut buf),
+                Err(EncodeError::InvalidExtension)
+            );
+        }
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut extensions = Extensions::new();
+        extensions.push("channel".to_string(), "news".to_string());
+        extensions.push("empty".to_string(), "".to_string());
+        let mut events = vec![
+            event("message", "Hello, world!", ""),
+            event("update", "multi\nline\n\ndata", "1"),
+            event("message", "", "1"),
+            event("message", " leading space", "2"),
+            event("message", "\n", ""),
+            event("emoji", "👍", "3"),
+        ];
+        events[1].retry = Some(Duration::from_millis(250));
+        events[5].extensions = extensions;
+
+        let mut encoder = EventEncoder::new();
+        let mut buf = String::new();
+        for event in &events {
+            encoder.encode_into(event, &mut buf).unwrap();
+        }
+
+        let mut parser = EventParser::new().with_extensions();
+        parser.feed(buf.as_bytes());
+        let mut parsed = Vec::new();
+        while let Some(event) = parser.next_event().unwrap() {
+            parsed.push(event);
+        }
+        assert_eq!(parsed, events);
+    }
+}
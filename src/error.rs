@@ -0,0 +1,97 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use core::fmt;
+use core::str::Utf8Error;
+
+/// Maximum number of bytes of input kept on either side of an error
+const EXCERPT_CONTEXT: usize = 32;
+
+/// The reason a [`ParseError`] occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A line is not valid UTF8
+    InvalidUtf8(Utf8Error),
+    /// A line does not match the event stream grammar
+    InvalidLine,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUtf8(_) => f.write_str("invalid UTF8"),
+            Self::InvalidLine => f.write_str("invalid line"),
+        }
+    }
+}
+
+/// Error for input which could not be parsed as an event stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: u64,
+    line: u64,
+    excerpt: String,
+}
+
+impl ParseError {
+    /// Create an error for the given `position` in `line`, which starts at `offset` bytes into
+    /// the stream and is the `number`th line
+    pub(crate) fn new(
+        kind: ParseErrorKind,
+        line: &[u8],
+        position: usize,
+        offset: u64,
+        number: u64,
+    ) -> Self {
+        let start = position.saturating_sub(EXCERPT_CONTEXT);
+        let end = line.len().min(position + EXCERPT_CONTEXT);
+        Self {
+            kind,
+            offset: offset + position as u64,
+            line: number,
+            excerpt: String::from_utf8_lossy(&line[start..end]).into_owned(),
+        }
+    }
+
+    /// Why parsing failed
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Offset in bytes from the start of the stream at which the error occurred
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Number of the line containing the error, starting at 1
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    /// A short, lossily decoded excerpt of the input around the error
+    pub fn excerpt(&self) -> &str {
+        &self.excerpt
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "{} at line {} (byte {}): {:?}",
+            self.kind, self.line, self.offset, self.excerpt
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::InvalidUtf8(err) => Some(err),
+            ParseErrorKind::InvalidLine => None,
+        }
+    }
+}
@@ -1,17 +1,87 @@
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 
 use core::time::Duration;
 
 /// An Event
+///
+/// Fields are [`String`]s by default. With the `bytes` feature enabled an `Event<ByteStr>` can
+/// be parsed instead, whose fields share the memory of the chunks they were received in.
 #[derive(Default, Debug, Eq, PartialEq, Clone)]
-pub struct Event {
+pub struct Event<T = String> {
     /// The event name if given
-    pub event: String,
+    pub event: T,
     /// The event data
-    pub data: String,
+    pub data: T,
     /// The event id if given
-    pub id: String,
+    pub id: T,
     /// Retry duration if given
     pub retry: Option<Duration>,
+    /// Fields which are not part of the spec. Only collected when enabled with
+    /// [`crate::EventParser::with_extensions`]
+    pub extensions: Extensions<T>,
+}
+
+/// Unrecognised fields of an event, such as `channel` or `traceparent`, in the order they were
+/// received
+#[derive(Default, Debug, Eq, PartialEq, Clone)]
+pub struct Extensions<T = String> {
+    fields: Vec<(T, T)>,
+}
+
+impl<T: AsRef<str>> Extensions<T> {
+    /// Create an empty set of extensions
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Get the value of a field. If the field was given several times the last value is
+    /// returned, just like for the standard fields.
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.fields
+            .iter()
+            .rev()
+            .find(|(field, _)| field.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Get all values of a field in the order they were received
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a T> {
+        self.fields
+            .iter()
+            .filter(move |(field, _)| field.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Append a field
+    pub fn push(&mut self, name: T, value: T) {
+        self.fields.push((name, value));
+    }
+
+    /// Iterate over all fields and their values in the order they were received
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.fields.iter().map(|(field, value)| (field, value))
+    }
+
+    /// Number of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether there are no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// An item of an event stream, as yielded by [`crate::FrameStream`]
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Frame<T = String> {
+    /// A dispatched event
+    Event(Event<T>),
+    /// A comment line, without its leading colon. Servers often send these as keep-alives.
+    Comment(T),
+    /// A block which set the reconnection time without carrying any data, so no event was
+    /// dispatched for it
+    Retry(Duration),
 }
@@ -0,0 +1,162 @@
+use crate::event::Event;
+use crate::event_parser::EventParser;
+use crate::event_stream::EventStreamError;
+use crate::limits::Limits;
+use std::io::{self, BufRead};
+
+/// A blocking Iterator of events read from a [`BufRead`]
+///
+/// Events are parsed exactly like in an [`crate::EventStream`], so errors don't end the
+/// iterator either. Errors of the reader are returned as [`EventStreamError::Transport`], except
+/// for [`io::ErrorKind::Interrupted`] on which the read is retried. A [`std::io::Read`] such as
+/// a file or the body of a blocking HTTP response can be wrapped in a [`std::io::BufReader`].
+///
+/// ```
+/// use eventsource_stream::EventIter;
+///
+/// let input: &[u8] = b"data: first\n\ndata: second\n\n";
+/// let events = EventIter::new(input)
+///     .map(|result| result.map(|event| event.data))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(events, vec!["first", "second"]);
+/// ```
+#[derive(Debug)]
+pub struct EventIter<R> {
+    reader: R,
+    parser: EventParser,
+}
+
+impl<R: BufRead> EventIter<R> {
+    /// Initialize the EventIter with a reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: EventParser::new(),
+        }
+    }
+
+    /// Collect fields other than `event`, `data`, `id` and `retry` into
+    /// [`Event::extensions`] instead of ignoring them
+    pub fn with_extensions(mut self) -> Self {
+        self.parser = self.parser.with_extensions();
+        self
+    }
+
+    /// Decode invalid UTF8 sequences to U+FFFD REPLACEMENT CHARACTER, as the HTML spec
+    /// requires, instead of failing with [`crate::ParseErrorKind::InvalidUtf8`]
+    pub fn with_lossy_utf8(mut self) -> Self {
+        self.parser = self.parser.with_lossy_utf8();
+        self
+    }
+
+    /// Enforce memory limits while parsing
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.parser = self.parser.with_limits(limits);
+        self
+    }
+
+    /// Set the last event ID of the iterator. Useful for initializing it with a previous last
+    /// event ID
+    pub fn set_last_event_id(&mut self, id: impl Into<String>) {
+        self.parser.set_last_event_id(id);
+    }
+
+    /// Get the last event ID of the iterator
+    pub fn last_event_id(&self) -> &str {
+        self.parser.last_event_id()
+    }
+
+    /// Unwrap the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for EventIter<R> {
+    type Item = Result<Event, EventStreamError<io::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.parser.next_event() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Err(err) => return Some(Err(err.into())),
+                Ok(None) => {}
+            }
+
+            if self.parser.is_finished() {
+                return None;
+            }
+
+            let len = match self.reader.fill_buf() {
+                Ok([]) => {
+                    if let Err(err) = self.parser.finish() {
+                        return Some(Err(err.into()));
+                    }
+                    continue;
+                }
+                Ok(buf) => {
+                    self.parser.feed(buf);
+                    buf.len()
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(EventStreamError::Transport(err))),
+            };
+            self.reader.consume(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{Trickle, INPUT};
+    use crate::{EventStream, LimitAction};
+    use futures::prelude::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn same_as_stream() {
+        let limits = Limits::new()
+            .max_line_length(16)
+            .on_exceeded(LimitAction::Error);
+        let iter = EventIter::new(INPUT)
+            .with_limits(limits)
+            .collect::<Vec<_>>();
+        let stream = futures::executor::block_on(
+            EventStream::new(stream::iter(vec![Ok::<_, io::Error>(INPUT)]))
+                .with_limits(limits)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(iter.len(), 4);
+        assert_eq!(format!("{:?}", iter), format!("{:?}", stream));
+    }
+
+    #[test]
+    fn reader_errors() {
+        let reader = BufReader::new(Trickle {
+            input: INPUT,
+            errors: vec![io::ErrorKind::BrokenPipe, io::ErrorKind::Interrupted],
+        });
+        let results = EventIter::new(reader).with_lossy_utf8().collect::<Vec<_>>();
+        let events = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .map(|event| (event.data.as_str(), event.id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("first", "1"),
+                ("\u{FFFD}", "1"),
+                ("0123456789abcdef", "1"),
+                ("second", "1")
+            ]
+        );
+        assert!(matches!(
+            &results[..],
+            [.., Err(EventStreamError::Transport(err)), Ok(_)]
+                if err.kind() == io::ErrorKind::BrokenPipe
+        ));
+    }
+}
@@ -0,0 +1,1115 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "bytes")]
+use bytes::{Buf, Bytes};
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::event::{Event, Frame};
+use crate::limits::{LimitAction, Limits};
+use crate::parser::{line, RawEventLine};
+use crate::text::{private::Line, EventText};
+use core::fmt;
+use core::ops::Range;
+use core::time::Duration;
+use memchr::{memchr2, memrchr2};
+
+/// Data buffer of an event being built. Values are only joined once a second data line
+/// arrives, so that single line data can keep sharing the memory it was parsed from.
+#[derive(Default, Debug)]
+enum DataBuffer<T> {
+    #[default]
+    Empty,
+    Single(T),
+    Joined(String),
+}
+
+#[derive(Default, Debug)]
+struct EventBuilder<T> {
+    event: Event<T>,
+    data: DataBuffer<T>,
+    is_complete: bool,
+    /// Bytes of the fields added to the event so far, counted against
+    /// [`Limits::max_event_size`]
+    size: usize,
+    /// The event exceeded a limit, so its lines are ignored up to the blank line ending it
+    is_discarded: bool,
+}
+
+impl<T: EventText> EventBuilder<T> {
+    /// From the HTML spec
+    ///
+    /// -> If the field name is "event"
+    ///    Set the event type buffer to field value.
+    ///
+    /// -> If the field name is "data"
+    ///    Append the field value to the data buffer, then append a single U+000A LINE FEED (LF)
+    ///    character to the data buffer.
+    ///
+    /// -> If the field name is "id"
+    ///    If the field value does not contain U+0000 NULL, then set the last event ID buffer
+    ///    to the field value. Otherwise, ignore the field.
+    ///
+    /// -> If the field name is "retry"
+    ///    If the field value consists of only ASCII digits, then interpret the field value as
+    ///    an integer in base ten, and set the event stream's reconnection time to that integer.
+    ///    Otherwise, ignore the field.
+    ///
+    /// -> Otherwise
+    ///    The field is ignored.
+    ///
+    /// Ignored fields are kept as extensions if enabled in `options`.
+    fn add(
+        &mut self,
+        source: &Line,
+        line: RawEventLine,
+        options: &Options,
+    ) -> Result<(), EventParserError> {
+        if self.is_discarded {
+            self.is_complete = matches!(line, RawEventLine::Empty);
+            return Ok(());
+        }
+        match line {
+            RawEventLine::Field(field, val) => {
+                let val = val.unwrap_or("");
+                let size = match field {
+                    "data" if !matches!(self.data, DataBuffer::Empty) => val.len() + 1,
+                    "event" | "data" | "id" => val.len(),
+                    "retry" => 0,
+                    name if options.extensions => name.len() + val.len(),
+                    _ => 0,
+                };
+                self.size += size;
+                if let Some(max) = options.limits.max_event_size.filter(|max| self.size > *max) {
+                    return Err(EventParserError::EventTooLarge(max));
+                }
+                match field {
+                    "event" => {
+                        self.event.event = T::from_field(source, val);
+                    }
+                    "data" => {
+                        self.data = match core::mem::take(&mut self.data) {
+                            DataBuffer::Empty => DataBuffer::Single(T::from_field(source, val)),
+                            DataBuffer::Single(first) => {
+                                let first = first.as_ref();
+                                let mut data = String::with_capacity(first.len() + val.len() + 1);
+                                data.push_str(first);
+                                data.push('\u{000A}');
+                                data.push_str(val);
+                                DataBuffer::Joined(data)
+                            }
+                            DataBuffer::Joined(mut data) => {
+                                data.push('\u{000A}');
+                                data.push_str(val);
+                                DataBuffer::Joined(data)
+                            }
+                        };
+                    }
+                    "id" if !val.contains('\u{0000}') => {
+                        self.event.id = T::from_field(source, val);
+                    }
+                    "retry" => {
+                        if let Ok(val) = val.parse::<u64>() {
+                            self.event.retry = Some(Duration::from_millis(val))
+                        }
+                    }
+                    "id" => {}
+                    name => {
+                        if options.extensions {
+                            self.event
+                                .extensions
+                                .push(T::from_field(source, name), T::from_field(source, val));
+                        }
+                    }
+                }
+            }
+            RawEventLine::Comment(_) => {}
+            RawEventLine::Empty => self.is_complete = true,
+        }
+        Ok(())
+    }
+
+    /// From the HTML spec
+    ///
+    /// 1. Set the last event ID string of the event source to the value of the last event ID
+    ///    buffer. The buffer does not get reset, so the last event ID string of the event source
+    ///    remains set to this value until the next time it is set by the server.
+    /// 2. If the data buffer is an empty string, set the data buffer and the event type buffer
+    ///    to the empty string and return.
+    /// 3. If the data buffer's last character is a U+000A LINE FEED (LF) character, then remove
+    ///    the last character from the data buffer.
+    /// 4. Let event be the result of creating an event using MessageEvent, in the relevant Realm
+    ///    of the EventSource object.
+    /// 5. Initialize event's type attribute to message, its data attribute to data, its origin
+    ///    attribute to the serialization of the origin of the event stream's final URL (i.e., the
+    ///    URL after redirects), and its lastEventId attribute to the last event ID string of the
+    ///    event source.
+    /// 6. If the event type buffer has a value other than the empty string, change the type of
+    ///    the newly created event to equal the value of the event type buffer.
+    /// 7. Set the data buffer and the event type buffer to the empty string.
+    /// 8. Queue a task which, if the readyState attribute is set to a value other than CLOSED,
+    ///    dispatches the newly created event at the EventSource object.
+    ///
+    /// The data buffer never holds the trailing LF of step 3, since lines are joined on demand.
+    ///
+    /// A block without data which set the reconnection time is returned as a [`Frame::Retry`].
+    fn dispatch(&mut self) -> Option<Frame<T>> {
+        let builder = core::mem::take(self);
+        let mut event = builder.event;
+        self.event.id = event.id.clone();
+
+        if builder.is_discarded {
+            return None;
+        }
+
+        event.data = match builder.data {
+            DataBuffer::Empty => return event.retry.map(Frame::Retry),
+            DataBuffer::Single(data) => data,
+            DataBuffer::Joined(data) => data.into(),
+        };
+
+        if event.event.as_ref().is_empty() {
+            event.event = T::from_static("message");
+        }
+
+        Some(Frame::Event(event))
+    }
+}
+
+/// Error thrown by an [`EventParser`]
+#[derive(Debug, PartialEq)]
+pub enum EventParserError {
+    /// Source bytes are not a valid event stream
+    Parser(ParseError),
+    /// A line exceeded [`Limits::max_line_length`], carrying the limit
+    LineTooLong(usize),
+    /// An event exceeded [`Limits::max_event_size`], carrying the limit
+    EventTooLarge(usize),
+    /// More bytes were buffered than [`Limits::max_buffer_size`] allows, carrying the limit
+    BufferFull(usize),
+}
+
+impl From<ParseError> for EventParserError {
+    fn from(err: ParseError) -> Self {
+        Self::Parser(err)
+    }
+}
+
+impl fmt::Display for EventParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parser(err) => f.write_fmt(format_args!("Parse error: {}", err)),
+            Self::LineTooLong(max) => {
+                f.write_fmt(format_args!("Line exceeds the limit of {} bytes", max))
+            }
+            Self::EventTooLarge(max) => {
+                f.write_fmt(format_args!("Event exceeds the limit of {} bytes", max))
+            }
+            Self::BufferFull(max) => {
+                f.write_fmt(format_args!("Buffer exceeds the limit of {} bytes", max))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EventParserError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parser(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Count the end-of-lines in `bytes`, where CRLF counts once
+fn count_lines(bytes: &[u8]) -> u64 {
+    let mut count = 0;
+    let mut rest = bytes;
+    while let Some(end) = memchr2(b'\r', b'\n', rest) {
+        count += 1;
+        let skip = if rest[end..].starts_with(b"\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = &rest[end + skip..];
+    }
+    count
+}
+
+/// Whether `bytes` end in a blank line, which ends the event before it
+fn ends_event(bytes: &[u8]) -> bool {
+    let line = match bytes {
+        [line @ .., b'\r', b'\n'] | [line @ .., b'\r' | b'\n'] => line,
+        _ => return false,
+    };
+    matches!(line.last(), Some(b'\r' | b'\n'))
+}
+
+/// Opt-in behaviour of an [`EventParser`]
+#[derive(Default, Debug, Clone)]
+pub(crate) struct Options {
+    pub(crate) extensions: bool,
+    pub(crate) lossy_utf8: bool,
+    pub(crate) limits: Limits,
+}
+
+/// UTF8 encoding of U+FEFF BYTE ORDER MARK
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Buffered bytes of the stream, split into lines
+#[derive(Default, Debug)]
+struct LineBuffer {
+    buffer: Vec<u8>,
+    /// A chunk which is used in place of `buffer` as long as no other bytes need to be joined
+    /// to it
+    #[cfg(feature = "bytes")]
+    shared: Bytes,
+    /// Start of the unconsumed bytes
+    pos: usize,
+    /// Offset of the first buffered byte from the start of the stream
+    offset: u64,
+    /// Number of lines consumed so far
+    line_count: u64,
+    /// Bytes from `pos` up to here are known to contain no end-of-line
+    scanned: usize,
+    /// The last line ended in a CR at the end of the buffer, so a leading LF must be skipped
+    pending_cr: bool,
+    /// Bytes up to the next end-of-line belong to a line which exceeded the limits and are
+    /// dropped
+    skipping: bool,
+    /// Lossily decoded text of the current line, if it was not valid UTF8
+    decoded: String,
+    started: bool,
+    finished: bool,
+}
+
+impl LineBuffer {
+    fn bytes(&self) -> &[u8] {
+        #[cfg(feature = "bytes")]
+        if !self.shared.is_empty() {
+            return &self.shared;
+        }
+        &self.buffer
+    }
+
+    /// Drop all unconsumed bytes, along with the rest of the line they end in
+    fn discard(&mut self) {
+        self.pos = self.bytes().len();
+        self.scanned = self.pos;
+        self.compact();
+        self.pending_cr = false;
+        self.skipping = true;
+    }
+
+    /// Number of buffered bytes which have not been parsed yet
+    fn unconsumed(&self) -> usize {
+        self.bytes().len() - self.pos
+    }
+
+    /// Drop all unconsumed bytes together with `chunk`, which is not buffered. Lines resume
+    /// after the end-of-line the chunk ends in, or after the next one.
+    fn drop_chunk(&mut self, chunk: &[u8]) {
+        let len = self.bytes().len();
+        self.line_count += count_lines(&self.bytes()[self.pos..]) + count_lines(chunk);
+        self.pos = len;
+        self.scanned = len;
+        self.compact();
+        self.offset += chunk.len() as u64;
+        self.started = true;
+        self.pending_cr = chunk.last() == Some(&b'\r');
+        self.skipping = !matches!(chunk.last(), Some(b'\r' | b'\n'));
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        self.compact();
+        #[cfg(feature = "bytes")]
+        self.unshare();
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    #[cfg(feature = "bytes")]
+    fn extend_shared(&mut self, chunk: Bytes) {
+        self.compact();
+        if self.bytes().is_empty() {
+            self.shared = chunk;
+        } else {
+            self.unshare();
+            self.buffer.extend_from_slice(&chunk);
+        }
+    }
+
+    /// Copy the shared chunk into the owned buffer so more bytes can be appended to it
+    #[cfg(feature = "bytes")]
+    fn unshare(&mut self) {
+        if !self.shared.is_empty() {
+            self.buffer.extend_from_slice(&self.shared);
+            self.shared = Bytes::new();
+        }
+    }
+
+    /// Mark the end of the stream. Unless `lossy` is set, the partial line after the last
+    /// end-of-line is checked to be valid UTF8 and dropped if it isn't, while complete lines
+    /// are left to be parsed.
+    fn finish(&mut self, lossy: bool) -> Result<(), ParseError> {
+        self.finished = true;
+        let bytes = self.bytes();
+        let start = match memrchr2(b'\r', b'\n', &bytes[self.scanned..]) {
+            Some(end) => self.scanned + end + 1,
+            None => self.pos,
+        };
+        // The rest of a line which exceeded the limits is dropped anyway
+        if lossy || (self.skipping && start == self.pos) {
+            return Ok(());
+        }
+        let len = bytes.len();
+        if let Err(err) = core::str::from_utf8(&bytes[start..]) {
+            // A LF completing a CRLF which was already counted doesn't end another line
+            let skip = (self.pending_cr && bytes.get(self.pos) == Some(&b'\n')) as usize;
+            let number = self.line_count + 1 + count_lines(&bytes[self.pos + skip..start]);
+            let err = self.error(
+                ParseErrorKind::InvalidUtf8(err),
+                start..len,
+                err.valid_up_to(),
+                number,
+            );
+            self.truncate(start);
+            self.scanned = self.scanned.min(start);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Drop the buffered bytes from `len` on
+    fn truncate(&mut self, len: usize) {
+        #[cfg(feature = "bytes")]
+        if !self.shared.is_empty() {
+            self.shared.truncate(len);
+            return;
+        }
+        self.buffer.truncate(len);
+    }
+
+    /// Find the next complete line in the buffer and consume it along with its end-of-line,
+    /// returning the range of the line contents. A line exceeding the `limits` is consumed
+    /// without being returned.
+    fn next_line(&mut self, limits: &Limits) -> Option<Result<Range<usize>, EventParserError>> {
+        if !self.started {
+            let rest = &self.bytes()[self.pos..];
+            if rest.len() < BOM.len() && BOM.starts_with(rest) && !self.finished {
+                return None;
+            }
+            let has_bom = rest.starts_with(BOM);
+            self.started = true;
+            if has_bom {
+                self.pos += BOM.len();
+                self.scanned = self.pos;
+            }
+        }
+
+        loop {
+            if self.pending_cr {
+                match self.bytes().get(self.pos) {
+                    Some(b'\n') => {
+                        self.pos += 1;
+                        self.scanned = self.pos;
+                    }
+                    Some(_) => {}
+                    None => return None,
+                }
+                self.pending_cr = false;
+            }
+
+            let bytes = self.bytes();
+            let start = self.pos;
+            let end = match memchr2(b'\r', b'\n', &bytes[self.scanned..]) {
+                Some(offset) => self.scanned + offset,
+                None => {
+                    let len = bytes.len();
+                    self.scanned = len;
+                    if self.skipping {
+                        self.pos = len;
+                    } else if let Some(max) =
+                        limits.max_line_length.filter(|max| len - start > *max)
+                    {
+                        self.discard();
+                        return Some(Err(EventParserError::LineTooLong(max)));
+                    }
+                    return None;
+                }
+            };
+            let mut pos = end + 1;
+            if bytes[end] == b'\r' {
+                match bytes.get(pos) {
+                    Some(b'\n') => pos += 1,
+                    Some(_) => {}
+                    None => self.pending_cr = true,
+                }
+            }
+            self.pos = pos;
+            self.scanned = pos;
+            self.line_count += 1;
+
+            if self.skipping {
+                self.skipping = false;
+                continue;
+            }
+            if let Some(max) = limits.max_line_length.filter(|max| end - start > *max) {
+                return Some(Err(EventParserError::LineTooLong(max)));
+            }
+            return Some(Ok(start..end));
+        }
+    }
+
+    /// Get a line previously returned by [`LineBuffer::next_line`]. If `lossy` is set, invalid
+    /// UTF8 sequences are replaced with U+FFFD REPLACEMENT CHARACTER instead of failing.
+    fn line(&mut self, range: Range<usize>, lossy: bool) -> Result<Line<'_>, ParseError> {
+        if let Err(err) = core::str::from_utf8(&self.bytes()[range.clone()]) {
+            if !lossy {
+                return Err(self.error(
+                    ParseErrorKind::InvalidUtf8(err),
+                    range,
+                    err.valid_up_to(),
+                    self.line_count,
+                ));
+            }
+            self.decoded = String::from_utf8_lossy(&self.bytes()[range]).into_owned();
+            return Ok(Line {
+                text: &self.decoded,
+                #[cfg(feature = "bytes")]
+                shared: None,
+            });
+        }
+        Ok(Line {
+            // SAFETY: the line was checked to be valid UTF8 above
+            text: unsafe { core::str::from_utf8_unchecked(&self.bytes()[range]) },
+            #[cfg(feature = "bytes")]
+            shared: Some(&self.shared).filter(|shared| !shared.is_empty()),
+        })
+    }
+
+    /// Create an error at `position` within the line at `range`
+    fn error(
+        &self,
+        kind: ParseErrorKind,
+        range: Range<usize>,
+        position: usize,
+        line_number: u64,
+    ) -> ParseError {
+        let offset = self.offset + range.start as u64;
+        ParseError::new(kind, &self.bytes()[range], position, offset, line_number)
+    }
+
+    /// Drop consumed bytes from the front of the buffer. Bytes are only moved once at least as
+    /// many have been consumed, which keeps the cost amortised linear in the input size.
+    fn compact(&mut self) {
+        if self.pos == 0 {
+            return;
+        }
+        #[cfg(feature = "bytes")]
+        if !self.shared.is_empty() {
+            self.shared.advance(self.pos);
+            self.offset += self.pos as u64;
+            self.scanned -= self.pos;
+            self.pos = 0;
+            return;
+        }
+        if self.pos == self.buffer.len() {
+            self.buffer.clear();
+        } else if self.pos >= self.buffer.len() - self.pos {
+            self.buffer.drain(..self.pos);
+        } else {
+            return;
+        }
+        self.offset += self.pos as u64;
+        self.scanned -= self.pos;
+        self.pos = 0;
+    }
+}
+
+/// A synchronous, push based event stream parser
+///
+/// The parser does no IO of its own: bytes are handed to it with [`EventParser::feed`] as they
+/// arrive and complete events are pulled out with [`EventParser::next_event`]. Chunks may be
+/// split anywhere, including in the middle of a UTF8 sequence. Once the source is exhausted call
+/// [`EventParser::finish`] and drain any remaining events.
+///
+/// Errors are not fatal. When a line can't be parsed or a limit is exceeded, the event it belongs
+/// to is dropped, the parser skips ahead to the blank line which ends that event and parsing
+/// continues from there, so later calls to [`EventParser::next_event`] yield the following events.
+///
+/// ```
+/// use eventsource_stream::EventParser;
+///
+/// let mut parser = EventParser::new();
+/// parser.feed(b"data: Hello,");
+/// assert_eq!(parser.next_event().unwrap(), None);
+///
+/// parser.feed(b" world!\n\n");
+/// let event = parser.next_event().unwrap().unwrap();
+/// assert_eq!(event.data, "Hello, world!");
+/// ```
+#[derive(Debug)]
+pub struct EventParser<T = String> {
+    lines: LineBuffer,
+    builder: EventBuilder<T>,
+    options: Options,
+    last_event_id: T,
+    /// A chunk exceeded [`Limits::max_buffer_size`], which is reported with the next event
+    buffer_full: Option<EventParserError>,
+}
+
+impl EventParser {
+    /// Initialize an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: EventText> Default for EventParser<T> {
+    fn default() -> Self {
+        Self {
+            lines: LineBuffer::default(),
+            builder: EventBuilder::default(),
+            options: Options::default(),
+            last_event_id: T::default(),
+            buffer_full: None,
+        }
+    }
+}
+
+impl<T: EventText> EventParser<T> {
+    /// Collect fields other than `event`, `data`, `id` and `retry` into
+    /// [`Event::extensions`] instead of ignoring them
+    pub fn with_extensions(mut self) -> Self {
+        self.options.extensions = true;
+        self
+    }
+
+    /// Decode invalid UTF8 sequences to U+FFFD REPLACEMENT CHARACTER, as the HTML spec
+    /// requires, instead of failing with [`crate::ParseErrorKind::InvalidUtf8`]
+    pub fn with_lossy_utf8(mut self) -> Self {
+        self.options.lossy_utf8 = true;
+        self
+    }
+
+    /// Enforce memory limits while parsing
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    pub(crate) fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the last event ID of the parser. Useful for initializing the parser with a previous
+    /// last event ID. Events without an `id` field will carry this ID.
+    pub fn set_last_event_id(&mut self, id: impl Into<String>) {
+        self.last_event_id = T::from(id.into());
+        self.builder.event.id = self.last_event_id.clone();
+    }
+
+    /// Get the last event ID, as set by the last dispatched block with or without data
+    pub fn last_event_id(&self) -> &str {
+        self.last_event_id.as_ref()
+    }
+
+    /// Push a chunk of bytes into the parser
+    ///
+    /// A chunk which takes the unparsed bytes over [`Limits::max_buffer_size`] is dropped
+    /// together with them, see there.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if !self.exceeds_buffer(bytes) {
+            self.lines.extend(bytes);
+        }
+    }
+
+    /// Push a chunk of shared bytes into the parser. Unlike [`EventParser::feed`] the chunk is
+    /// not copied as long as the parser holds no partial line from a previous chunk, and fields
+    /// of an `Event<ByteStr>` will point into it.
+    #[cfg(feature = "bytes")]
+    pub fn feed_bytes(&mut self, bytes: Bytes) {
+        if !self.exceeds_buffer(&bytes) {
+            self.lines.extend_shared(bytes);
+        }
+    }
+
+    /// Drop `chunk` and the unparsed bytes if together they exceed the buffer limit
+    fn exceeds_buffer(&mut self, chunk: &[u8]) -> bool {
+        let max = match self.options.limits.max_buffer_size {
+            Some(max) if self.lines.unconsumed() + chunk.len() > max => max,
+            _ => return false,
+        };
+        self.lines.drop_chunk(chunk);
+        if ends_event(chunk) {
+            self.builder = EventBuilder::default();
+            self.builder.event.id = self.last_event_id.clone();
+        } else {
+            self.discard_event();
+        }
+        self.buffer_full = Some(EventParserError::BufferFull(max));
+        true
+    }
+
+    /// Discard the current event after it exceeded a limit
+    fn exceeded(&mut self, err: EventParserError) -> Result<(), EventParserError> {
+        self.discard_event();
+        match self.options.limits.action {
+            LimitAction::Error => Err(err),
+            LimitAction::Discard => Ok(()),
+        }
+    }
+
+    /// Drop the current event and ignore its lines up to the blank line which ends it
+    fn discard_event(&mut self) {
+        self.builder = EventBuilder::default();
+        self.builder.event.id = self.last_event_id.clone();
+        self.builder.is_discarded = true;
+    }
+
+    /// Signal that no more bytes will be fed to the parser. Events which are still buffered can
+    /// then be drained with [`EventParser::next_event`], while an incomplete trailing event is
+    /// discarded as required by the spec.
+    pub fn finish(&mut self) -> Result<(), EventParserError> {
+        Ok(self.lines.finish(self.options.lossy_utf8)?)
+    }
+
+    /// Whether [`EventParser::finish`] has been called
+    pub fn is_finished(&self) -> bool {
+        self.lines.finished
+    }
+
+    /// Parse the next complete event out of the buffered bytes, if there is one
+    pub fn next_event(&mut self) -> Result<Option<Event<T>>, EventParserError> {
+        loop {
+            match self.parse_next(false)? {
+                Some(Frame::Event(event)) => return Ok(Some(event)),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Parse the next complete event, comment or retry-only block out of the buffered bytes,
+    /// if there is one
+    pub fn next_frame(&mut self) -> Result<Option<Frame<T>>, EventParserError> {
+        self.parse_next(true)
+    }
+
+    fn parse_next(&mut self, comments: bool) -> Result<Option<Frame<T>>, EventParserError> {
+        if let Some(err) = self.buffer_full.take() {
+            if self.options.limits.action == LimitAction::Error {
+                return Err(err);
+            }
+        }
+        while let Some(range) = self.lines.next_line(&self.options.limits) {
+            let range = match range {
+                Ok(range) => range,
+                Err(err) => {
+                    self.exceeded(err)?;
+                    continue;
+                }
+            };
+            let source = match self.lines.line(range.clone(), self.options.lossy_utf8) {
+                Ok(source) => source,
+                Err(err) => {
+                    self.discard_event();
+                    return Err(err.into());
+                }
+            };
+            match line(source.text) {
+                Ok((_, RawEventLine::Comment(comment))) if comments => {
+                    return Ok(Some(Frame::Comment(T::from_field(&source, comment))));
+                }
+                Ok((_, next_line)) => {
+                    if let Err(err) = self.builder.add(&source, next_line, &self.options) {
+                        self.exceeded(err)?;
+                        continue;
+                    }
+                }
+                Err(_) => {
+                    self.discard_event();
+                    let number = self.lines.line_count;
+                    return Err(self
+                        .lines
+                        .error(ParseErrorKind::InvalidLine, range, 0, number)
+                        .into());
+                }
+            }
+            if self.builder.is_complete {
+                let frame = self.builder.dispatch();
+                // Every dispatch sets the last event ID, even of a block without data
+                self.last_event_id = self.builder.event.id.clone();
+                if frame.is_some() {
+                    return Ok(frame);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::message;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<Result<Event, EventParserError>> {
+        let mut parser = EventParser::new();
+        let mut results = Vec::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+            while let Some(result) = parser.next_event().transpose() {
+                results.push(result);
+            }
+        }
+        if let Err(err) = parser.finish() {
+            results.push(Err(err));
+        }
+        while let Some(result) = parser.next_event().transpose() {
+            results.push(result);
+        }
+        results
+    }
+
+    #[test]
+    fn split_chunks() {
+        assert_eq!(
+            parse(&[b"data: Hello, world!\n\n"]),
+            vec![Ok(message("Hello, world!", ""))]
+        );
+        assert_eq!(
+            parse(&[b"da", b"ta: Hello,", b"", b" world!\n", b"\n"]),
+            vec![Ok(message("Hello, world!", ""))]
+        );
+        assert_eq!(
+            parse(&[b"data: \xf0\x9f", b"\x91\x8d\n\n"]),
+            vec![Ok(message("👍", ""))]
+        );
+        assert_eq!(
+            parse(&[b"data: \xf0", b"\x9f\x91", b"\x8d\xf0\x9f\x91\x8d\n\n"]),
+            vec![Ok(message("👍👍", ""))]
+        );
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        assert_eq!(
+            parse(&[b"\xef\xbb\xbfdata: first\n\n\xef\xbb\xbfdata: second\n\n"]),
+            vec![Ok(message("first", ""))]
+        );
+        assert_eq!(
+            parse(&[b"", b"\xef\xbb", b"\xbfdata: first\n\n"]),
+            vec![Ok(message("first", ""))]
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(
+            parse(&[b"data: a\r\ndata: b\rdata: c\n\r\n"]),
+            vec![Ok(message("a\nb\nc", ""))]
+        );
+        assert_eq!(
+            parse(&[b"data: a\r", b"\ndata: b\r", b"\r", b"\n"]),
+            vec![Ok(message("a\nb", ""))]
+        );
+    }
+
+    #[test]
+    fn many_lines() {
+        let mut parser = EventParser::new();
+        for i in 0..10_000 {
+            parser.feed(format!("data: {}\n", i).as_bytes());
+        }
+        parser.feed(b"\n");
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data.lines().count(), 10_000);
+        assert_eq!(event.data.lines().last(), Some("9999"));
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn trailing_carriage_return() {
+        assert_eq!(parse(&[b"data: cr\r\r"]), vec![Ok(message("cr", ""))]);
+        assert_eq!(parse(&[b"data: cr\r"]), vec![]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let results = parse(&[b"data: \xf0\x9f"]);
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            &results[0],
+            Err(EventParserError::Parser(err)) if err.line() == 1 && err.offset() == 6
+        ));
+
+        let results = parse(&[b"\xef\xbb\xbfdata: ok\n\n", b": comment\r\ndata: \xff\n\n"]);
+        assert_eq!(results.len(), 2);
+        let err = match &results[1] {
+            Err(EventParserError::Parser(err)) => err,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert!(matches!(err.kind(), ParseErrorKind::InvalidUtf8(_)));
+        assert_eq!(err.line(), 4);
+        assert_eq!(err.offset(), 30);
+        assert_eq!(err.excerpt(), "data: \u{fffd}");
+
+        let mut line = vec![b'x'; 1000];
+        line.extend_from_slice(b"\xff\n");
+        let results = parse(&[&line]);
+        let err = match &results[0] {
+            Err(EventParserError::Parser(err)) => err,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(err.offset(), 1000);
+        assert_eq!(err.excerpt().len(), 32 + '\u{fffd}'.len_utf8());
+    }
+
+    #[test]
+    fn finish_before_draining() {
+        let mut parser = EventParser::new();
+        parser.feed(b"data: a\n\ndata: b\r\n\r\ndata: \xf0\x9f");
+        let err = match parser.finish() {
+            Err(EventParserError::Parser(err)) => err,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert!(matches!(err.kind(), ParseErrorKind::InvalidUtf8(_)));
+        assert_eq!(err.line(), 5);
+        assert_eq!(err.offset(), 26);
+        assert_eq!(err.excerpt(), "data: \u{fffd}");
+        assert_eq!(parser.next_event(), Ok(Some(message("a", ""))));
+        assert_eq!(parser.next_event(), Ok(Some(message("b", ""))));
+        assert_eq!(parser.next_event(), Ok(None));
+    }
+
+    #[test]
+    fn lossy_utf8() {
+        let mut parser = EventParser::new().with_lossy_utf8();
+        let results = parse_with(
+            &mut parser,
+            &[
+                b"data: a\xffb\n\n",
+                b"event: \xf0\x9f",
+                b"\x91\x8d\xf0\x9f\ndata: c\xed\xa0\x80\n\n",
+                b"data: \xf0\x9f",
+            ],
+        );
+        let mut event = message("a\u{fffd}b", "");
+        assert_eq!(results[0], Ok(event.clone()));
+        event.event = "👍\u{fffd}".to_string();
+        event.data = "c\u{fffd}\u{fffd}\u{fffd}".to_string();
+        assert_eq!(results[1], Ok(event));
+        assert_eq!(results.len(), 2);
+        assert_eq!(parser.finish(), Ok(()));
+        assert_eq!(parser.next_event(), Ok(None));
+    }
+
+    #[test]
+    fn recovery() {
+        let results =
+            parse(&[b"id: 1\ndata: a\n\nid: 2\ndata: b\ndata: \xff\ndata: c\n\ndata: d\n\n"]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().data, "a");
+        assert!(matches!(results[1], Err(EventParserError::Parser(_))));
+        let event = results[2].as_ref().unwrap();
+        assert_eq!(event.data, "d");
+        assert_eq!(event.id, "1");
+
+        let mut parser = EventParser::new();
+        parser.feed(b"id: 1\ndata: a\n\nid: 2\ndata: b\xff\n\ndata: \xff\r\n\ndata: d\n\n");
+        assert_eq!(parser.next_event().unwrap().unwrap().id, "1");
+        assert!(parser.next_event().is_err());
+        assert!(parser.next_event().is_err());
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data, "d");
+        assert_eq!(event.id, "1");
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn frames() {
+        let mut parser = EventParser::new();
+        parser.feed(b": heartbeat\n\nretry: 500\n\ndata: first\n: inline\nretry: x\n\n:\n");
+        let mut frames = Vec::new();
+        while let Some(frame) = parser.next_frame().unwrap() {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            vec![
+                Frame::Comment(" heartbeat".to_string()),
+                Frame::Retry(Duration::from_millis(500)),
+                Frame::Comment(" inline".to_string()),
+                Frame::Event(message("first", "")),
+                Frame::Comment("".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let input = b"channel: news\ndata: first\ntraceparent: 00-abc\nchannel: sports\nbad\n\n";
+
+        let mut parser = EventParser::new();
+        parser.feed(input);
+        let event = parser.next_event().unwrap().unwrap();
+        assert!(event.extensions.is_empty());
+
+        let mut parser = EventParser::new().with_extensions();
+        parser.feed(input);
+        let event = parser.next_event().unwrap().unwrap();
+        assert_eq!(event.data, "first");
+        assert_eq!(event.extensions.len(), 4);
+        assert_eq!(event.extensions.get("channel").unwrap(), "sports");
+        assert_eq!(
+            event.extensions.get_all("channel").collect::<Vec<_>>(),
+            vec!["news", "sports"]
+        );
+        assert_eq!(event.extensions.get("traceparent").unwrap(), "00-abc");
+        assert_eq!(event.extensions.get("bad").unwrap(), "");
+        assert_eq!(
+            event
+                .extensions
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["channel", "traceparent", "channel", "bad"]
+        );
+    }
+
+    fn parse_with(
+        parser: &mut EventParser,
+        chunks: &[&[u8]],
+    ) -> Vec<Result<Event, EventParserError>> {
+        let mut results = Vec::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+            while let Some(result) = parser.next_event().transpose() {
+                results.push(result);
+            }
+        }
+        results
+    }
+
+    #[test]
+    fn line_length_limit() {
+        let chunks: &[&[u8]] = &[
+            b"data: first\n\nid: 1\ndata: 0123456",
+            b"789\ndata: more\n\ndata: last\n\ndata: 0123456789\n\n",
+        ];
+        let limits = Limits::new().max_line_length(12);
+
+        let mut parser = EventParser::new().with_limits(limits);
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![
+                Ok(message("first", "")),
+                Err(EventParserError::LineTooLong(12)),
+                Ok(message("last", "")),
+                Err(EventParserError::LineTooLong(12)),
+            ]
+        );
+        assert_eq!(parser.last_event_id(), "");
+
+        let mut parser = EventParser::new().with_limits(limits.on_exceeded(LimitAction::Discard));
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![Ok(message("first", "")), Ok(message("last", ""))]
+        );
+    }
+
+    #[test]
+    fn event_size_limit() {
+        let chunks: &[&[u8]] = &[b"data: 0123\ndata: 4567\ndata: 89\n\ndata: 0123\ndata: 456\n\n"];
+        let limits = Limits::new().max_event_size(8);
+
+        let mut parser = EventParser::new().with_limits(limits);
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![
+                Err(EventParserError::EventTooLarge(8)),
+                Ok(message("0123\n456", ""))
+            ]
+        );
+
+        let mut parser = EventParser::new().with_limits(limits.on_exceeded(LimitAction::Discard));
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![Ok(message("0123\n456", ""))]
+        );
+
+        // Extensions, the event type and the id count as well
+        let chunk = format!(
+            "{}data: x\n\nevent: 01234567\ndata: x\n\n",
+            "x: 0\n".repeat(100)
+        );
+        let mut parser = EventParser::new().with_limits(limits).with_extensions();
+        assert_eq!(
+            parse_with(&mut parser, &[chunk.as_bytes()]),
+            vec![
+                Err(EventParserError::EventTooLarge(8)),
+                Err(EventParserError::EventTooLarge(8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_limit() {
+        let chunks: &[&[u8]] = &[
+            b"data: first\n\n",
+            b"data: 0123456789",
+            b"0123456789",
+            b"\ndata: more\n\n",
+            b"data: last\n\n",
+        ];
+        let limits = Limits::new().max_buffer_size(24);
+
+        let mut parser = EventParser::new().with_limits(limits);
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![
+                Ok(message("first", "")),
+                Err(EventParserError::BufferFull(24)),
+                Ok(message("last", "")),
+            ]
+        );
+
+        let mut parser = EventParser::new().with_limits(limits.on_exceeded(LimitAction::Discard));
+        assert_eq!(
+            parse_with(&mut parser, chunks),
+            vec![Ok(message("first", "")), Ok(message("last", ""))]
+        );
+
+        // Complete lines count too, and parsing resumes after the blank line ending a chunk
+        let chunk = "data: 0\n\n".repeat(10);
+        let mut parser = EventParser::new().with_limits(limits);
+        assert_eq!(
+            parse_with(&mut parser, &[chunk.as_bytes(), b"data: after\n\n"]),
+            vec![
+                Err(EventParserError::BufferFull(24)),
+                Ok(message("after", ""))
+            ]
+        );
+    }
+
+    #[test]
+    fn last_event_id() {
+        let mut parser = EventParser::new();
+        parser.set_last_event_id("41");
+        parser.feed(b"data: resumed\n\nid: 42\ndata: next\n\n");
+        assert_eq!(parser.next_event().unwrap().unwrap().id, "41");
+        assert_eq!(parser.last_event_id(), "41");
+        assert_eq!(parser.next_event().unwrap().unwrap().id, "42");
+        assert_eq!(parser.last_event_id(), "42");
+
+        parser.feed(b"id: 43\n\ndata: after\n\n");
+        assert_eq!(parser.next_event().unwrap().unwrap().id, "43");
+        parser.feed(b"id: 44\n\n");
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert_eq!(parser.last_event_id(), "44");
+    }
+}
@@ -0,0 +1,666 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use crate::backoff::{BackoffPolicy, ConstantBackoff};
+use crate::event::{Event, Frame};
+use crate::event_parser::Options;
+use crate::event_stream::{EventStream, EventStreamError, FrameStream};
+use crate::limits::Limits;
+use crate::timer::Timer;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use pin_project_lite::pin_project;
+
+/// Connection state of an [`EventSource`], like the `readyState` of a browser `EventSource`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadyState {
+    /// A connection is being made, or will be made once the reconnection delay has passed
+    Connecting,
+    /// A connection is open and events are being received
+    Open,
+    /// The source won't connect again
+    Closed,
+}
+
+/// Why an [`EventSource`] was closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CloseReason {
+    /// [`EventSource::close`] was called
+    Requested,
+    /// The [`BackoffPolicy`] gave up reconnecting
+    RetriesExhausted,
+    /// A connection attempt failed with an error after which the [`Connector`] must not be
+    /// retried, such as a response which is not an event stream
+    Failed,
+}
+
+/// An item of an [`EventSource`]
+///
+/// Besides the events, every change of the [`ReadyState`] is yielded, in the same order as a
+/// browser would fire its `open` and `error` events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SourceItem {
+    /// A connection is being made
+    Connecting,
+    /// A connection was established
+    Open,
+    /// An event was received
+    Event(Event),
+    /// The connection ended or failed and a new one is made after the given delay
+    Reconnecting(Duration),
+    /// The source was closed and ends after this item
+    Closed(CloseReason),
+}
+
+pin_project! {
+#[project = StateProj]
+enum State<Fut, S, Sl> {
+    Idle,
+    Connecting {
+        #[pin]
+        future: Fut,
+    },
+    Open {
+        #[pin]
+        stream: FrameStream<S>,
+        // Fires once the connection counts as healthy
+        #[pin]
+        healthy: Option<Sl>,
+    },
+    Waiting {
+        #[pin]
+        sleep: Sl,
+        delay: Duration,
+        announced: bool,
+    },
+    Closed {
+        // Taken once the closing has been yielded
+        reason: Option<CloseReason>,
+    },
+}
+}
+
+/// Opens the connections of an [`EventSource`]
+///
+/// Implemented for closures which take the last event ID, if any, and return a future
+/// resolving to a stream of bytes. Implement the trait directly to also get the attempt number.
+pub trait Connector {
+    /// Stream of bytes of an open connection
+    type Stream;
+    /// Error of a connection attempt or of an open connection
+    type Error;
+    /// Future resolving to an open connection
+    type Future: Future<Output = Result<Self::Stream, Self::Error>>;
+
+    /// Start a connection attempt, resuming after `last_event_id`. The `attempt` is 0 for the
+    /// first connection and otherwise counts the reconnects since the last healthy connection,
+    /// as passed to the [`BackoffPolicy`].
+    fn connect(&mut self, last_event_id: Option<String>, attempt: u32) -> Self::Future;
+
+    /// Whether a failed connection attempt must not be retried. The HTML spec requires this
+    /// for responses with a status other than `200 OK`, or which are not an event stream.
+    fn is_fatal(&self, _err: &Self::Error) -> bool {
+        false
+    }
+
+    /// Take the [`crate::ResponseError`] out of a failed connection attempt whose response was
+    /// rejected. It is yielded as [`EventStreamError::Response`] and the source is closed.
+    #[cfg(feature = "http")]
+    fn response_error(&self, err: Self::Error) -> Result<crate::ResponseError, Self::Error> {
+        Err(err)
+    }
+}
+
+impl<F, Fut, S, E> Connector for F
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<S, E>>,
+{
+    type Stream = S;
+    type Error = E;
+    type Future = Fut;
+
+    fn connect(&mut self, last_event_id: Option<String>, _attempt: u32) -> Fut {
+        self(last_event_id)
+    }
+}
+
+pin_project! {
+/// A Stream of events which reconnects whenever its source ends or fails
+///
+/// Connections are made by calling the [`Connector`] with the last event ID received so far, if
+/// any, which should be sent as the `Last-Event-ID` header. Once a connection ends or fails, the
+/// source waits for a delay chosen by its [`BackoffPolicy`] and then connects again. By default
+/// this is the reconnection time given by the server, or 3 seconds, and the source never gives
+/// up. The events of all connections are yielded as a single stream along with
+/// [`SourceItem`]s marking the connection changes, and transport errors are passed on before
+/// reconnecting.
+///
+/// ```no_run
+/// use eventsource_stream::{EventSource, SourceItem};
+/// use futures::prelude::*;
+/// use std::time::Duration;
+///
+/// # async fn run() {
+/// let client = reqwest::Client::new();
+/// let source = EventSource::new(
+///     move |last_event_id: Option<String>| {
+///         let mut request = client.get("http://localhost:7020/notifications");
+///         if let Some(id) = last_event_id {
+///             request = request.header("Last-Event-ID", id);
+///         }
+///         async move { Ok::<_, reqwest::Error>(request.send().await?.bytes_stream()) }
+///     },
+///     |duration: Duration| async move {
+///         // tokio::time::sleep(duration).await
+///     },
+/// );
+/// futures::pin_mut!(source);
+///
+/// while let Some(item) = source.next().await {
+///     match item {
+///         Ok(SourceItem::Event(event)) => println!("{}", event.data),
+///         Ok(item) => println!("{:?}", item),
+///         Err(err) => eprintln!("error occured: {}", err),
+///     }
+/// }
+/// # }
+/// ```
+#[project = EventSourceProj]
+pub struct EventSource<C: Connector, Tm: Timer, P = ConstantBackoff> {
+    connector: C,
+    timer: Tm,
+    backoff: P,
+    // Number of reconnects since the last healthy connection
+    attempt: u32,
+    last_event_id: String,
+    retry: Option<Duration>,
+    options: Options,
+    #[pin]
+    state: State<C::Future, C::Stream, Tm::Sleep>,
+}
+}
+
+impl<C: Connector, Tm: Timer> EventSource<C, Tm> {
+    /// Create an event source which connects with `connector` and waits between connections
+    /// using `timer`. No connection is made before the source is first polled.
+    pub fn new(connector: C, timer: Tm) -> Self {
+        Self {
+            connector,
+            timer,
+            backoff: ConstantBackoff::default(),
+            attempt: 0,
+            last_event_id: String::new(),
+            retry: None,
+            options: Options::default(),
+            state: State::Idle,
+        }
+    }
+
+    /// Choose how long to wait before reconnecting and when to give up
+    pub fn with_backoff<P: BackoffPolicy>(self, backoff: P) -> EventSource<C, Tm, P> {
+        EventSource {
+            connector: self.connector,
+            timer: self.timer,
+            backoff,
+            attempt: self.attempt,
+            last_event_id: self.last_event_id,
+            retry: self.retry,
+            options: self.options,
+            state: State::Idle,
+        }
+    }
+}
+
+impl<C: Connector, Tm: Timer, P> EventSource<C, Tm, P> {
+    /// Collect fields other than `event`, `data`, `id` and `retry` into
+    /// [`Event::extensions`] instead of ignoring them
+    pub fn with_extensions(mut self) -> Self {
+        self.options.extensions = true;
+        self
+    }
+
+    /// Decode invalid UTF8 sequences to U+FFFD REPLACEMENT CHARACTER, as the HTML spec
+    /// requires, instead of failing with [`crate::ParseErrorKind::InvalidUtf8`]
+    pub fn with_lossy_utf8(mut self) -> Self {
+        self.options.lossy_utf8 = true;
+        self
+    }
+
+    /// Enforce memory limits while parsing the events of every connection
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Set the last event ID of the source. Useful for resuming from a previously received
+    /// event.
+    pub fn set_last_event_id(&mut self, id: impl Into<String>) {
+        self.last_event_id = id.into();
+    }
+
+    /// Get the ID of the last received event
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    /// Get the last reconnection time sent by the server
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Get the current connection state
+    pub fn ready_state(&self) -> ReadyState {
+        match self.state {
+            State::Idle | State::Connecting { .. } | State::Waiting { .. } => {
+                ReadyState::Connecting
+            }
+            State::Open { .. } => ReadyState::Open,
+            State::Closed { .. } => ReadyState::Closed,
+        }
+    }
+
+    /// Close the source, dropping its connection. The stream yields
+    /// [`SourceItem::Closed`] and then ends.
+    pub fn close(self: Pin<&mut Self>) {
+        let mut state = self.project().state;
+        if !matches!(*state, State::Closed { .. }) {
+            state.set(State::Closed {
+                reason: Some(CloseReason::Requested),
+            });
+        }
+    }
+}
+
+impl<C: Connector, Tm: Timer, P: BackoffPolicy> EventSourceProj<'_, C, Tm, P> {
+    /// Schedule the next connection after the current one ended or failed to open
+    fn reconnect(&mut self) {
+        *self.attempt = self.attempt.saturating_add(1);
+        match self.backoff.delay(*self.attempt, *self.retry) {
+            Some(delay) => self.state.set(State::Waiting {
+                sleep: self.timer.sleep(delay),
+                delay,
+                announced: false,
+            }),
+            None => self.state.set(State::Closed {
+                reason: Some(CloseReason::RetriesExhausted),
+            }),
+        }
+    }
+}
+
+impl<C, B, Tm, P> Stream for EventSource<C, Tm, P>
+where
+    C: Connector,
+    C::Stream: Stream<Item = Result<B, C::Error>>,
+    B: AsRef<[u8]>,
+    Tm: Timer,
+    P: BackoffPolicy,
+{
+    type Item = Result<SourceItem, EventStreamError<C::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            match this.state.as_mut().project() {
+                StateProj::Idle => {
+                    let last_event_id =
+                        Some(this.last_event_id.clone()).filter(|id| !id.is_empty());
+                    let future = this.connector.connect(last_event_id, *this.attempt);
+                    this.state.set(State::Connecting { future });
+                    return Poll::Ready(Some(Ok(SourceItem::Connecting)));
+                }
+                StateProj::Connecting { future } => match future.poll(cx) {
+                    Poll::Ready(Ok(stream)) => {
+                        let mut stream =
+                            EventStream::new(stream).with_options(this.options.clone());
+                        stream.set_last_event_id(this.last_event_id.as_str());
+                        let healthy = match this.backoff.healthy_period() {
+                            Some(period) => Some(this.timer.sleep(period)),
+                            None => {
+                                *this.attempt = 0;
+                                None
+                            }
+                        };
+                        this.state.set(State::Open {
+                            stream: stream.frames(),
+                            healthy,
+                        });
+                        return Poll::Ready(Some(Ok(SourceItem::Open)));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        #[cfg(feature = "http")]
+                        let err = match this.connector.response_error(err) {
+                            Ok(err) => {
+                                this.state.set(State::Closed {
+                                    reason: Some(CloseReason::Failed),
+                                });
+                                return Poll::Ready(Some(Err(EventStreamError::Response(err))));
+                            }
+                            Err(err) => err,
+                        };
+                        if this.connector.is_fatal(&err) {
+                            this.state.set(State::Closed {
+                                reason: Some(CloseReason::Failed),
+                            });
+                        } else {
+                            this.reconnect();
+                        }
+                        return Poll::Ready(Some(Err(EventStreamError::Transport(err))));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                StateProj::Open {
+                    mut stream,
+                    mut healthy,
+                } => {
+                    // The healthy period counts from the opening, so it is polled on every
+                    // wakeup to start timers which only start once polled
+                    if let Some(sleep) = healthy.as_mut().as_pin_mut() {
+                        if sleep.poll(cx).is_ready() {
+                            healthy.set(None);
+                            *this.attempt = 0;
+                        }
+                    }
+                    let poll = stream.as_mut().poll_next(cx);
+                    // Blocks with an id but no data set the last event ID without a frame
+                    if stream.last_event_id() != this.last_event_id.as_str() {
+                        this.last_event_id.clear();
+                        this.last_event_id.push_str(stream.last_event_id());
+                    }
+                    let result = match poll {
+                        Poll::Ready(Some(Ok(Frame::Event(event)))) => {
+                            if event.retry.is_some() {
+                                *this.retry = event.retry;
+                            }
+                            return Poll::Ready(Some(Ok(SourceItem::Event(event))));
+                        }
+                        Poll::Ready(Some(Ok(Frame::Retry(retry)))) => {
+                            *this.retry = Some(retry);
+                            continue;
+                        }
+                        Poll::Ready(Some(Ok(Frame::Comment(_)))) => continue,
+                        Poll::Ready(Some(Err(EventStreamError::Transport(err)))) => {
+                            Some(EventStreamError::Transport(err))
+                        }
+                        Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                        Poll::Ready(None) => None,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    this.reconnect();
+                    if let Some(err) = result {
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+                StateProj::Waiting {
+                    sleep,
+                    delay,
+                    announced,
+                } => {
+                    if !*announced {
+                        *announced = true;
+                        return Poll::Ready(Some(Ok(SourceItem::Reconnecting(*delay))));
+                    }
+                    match sleep.poll(cx) {
+                        Poll::Ready(()) => this.state.set(State::Idle),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                StateProj::Closed { reason } => {
+                    return Poll::Ready(reason.take().map(|reason| Ok(SourceItem::Closed(reason))))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use futures::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn message(data: &str, id: &str) -> SourceItem {
+        SourceItem::Event(test_util::message(data, id))
+    }
+
+    type Connection = Result<Vec<Result<&'static [u8], &'static str>>, &'static str>;
+
+    #[tokio::test]
+    async fn reconnects() {
+        let connections: Vec<Connection> = vec![
+            Ok(vec![Ok(b"retry: 100\n\nid: 1\ndata: first\n\n")]),
+            Err("refused"),
+            Ok(vec![
+                Ok(b"data: second\n\nid: 2\n\nid: 3\ndata: th"),
+                Err("reset"),
+            ]),
+            Ok(vec![Ok(b"data: third\n\n")]),
+        ];
+        let connections = RefCell::new(connections.into_iter());
+        let last_event_ids = Rc::new(RefCell::new(Vec::new()));
+        let delays = Rc::new(RefCell::new(Vec::new()));
+
+        let ids = last_event_ids.clone();
+        let sleeps = delays.clone();
+        let items = EventSource::new(
+            move |last_event_id| {
+                ids.borrow_mut().push(last_event_id);
+                let connection = connections.borrow_mut().next().unwrap();
+                future::ready(connection.map(stream::iter))
+            },
+            move |delay| {
+                sleeps.borrow_mut().push(delay);
+                future::ready(())
+            },
+        )
+        .take(16)
+        .collect::<Vec<_>>()
+        .await;
+
+        assert_eq!(
+            items,
+            vec![
+                Ok(SourceItem::Connecting),
+                Ok(SourceItem::Open),
+                Ok(message("first", "1")),
+                Ok(SourceItem::Reconnecting(Duration::from_millis(100))),
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport("refused")),
+                Ok(SourceItem::Reconnecting(Duration::from_millis(100))),
+                Ok(SourceItem::Connecting),
+                Ok(SourceItem::Open),
+                Ok(message("second", "1")),
+                Err(EventStreamError::Transport("reset")),
+                Ok(SourceItem::Reconnecting(Duration::from_millis(100))),
+                Ok(SourceItem::Connecting),
+                Ok(SourceItem::Open),
+                Ok(message("third", "2")),
+                Ok(SourceItem::Reconnecting(Duration::from_millis(100))),
+            ]
+        );
+        assert_eq!(
+            *last_event_ids.borrow(),
+            vec![
+                None,
+                Some("1".to_string()),
+                Some("1".to_string()),
+                Some("2".to_string())
+            ]
+        );
+        assert_eq!(delays.borrow().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn parser_options() {
+        let chunk: &'static [u8] = b"data: a\xffb\n\ndata: 0123456789\n\n";
+        let items = EventSource::new(
+            move |_| future::ready(Ok::<_, &str>(stream::iter(vec![Ok(chunk)]))),
+            |_| future::ready(()),
+        )
+        .with_lossy_utf8()
+        .with_limits(Limits::new().max_line_length(10))
+        .take(10)
+        .collect::<Vec<_>>()
+        .await;
+
+        // Every connection parses with the same options
+        for connection in items.chunks(5) {
+            assert_eq!(
+                connection,
+                [
+                    Ok(SourceItem::Connecting),
+                    Ok(SourceItem::Open),
+                    Ok(message("a\u{fffd}b", "")),
+                    Err(EventStreamError::LineTooLong(10)),
+                    Ok(SourceItem::Reconnecting(Duration::from_secs(3))),
+                ]
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff() {
+        use crate::ExponentialBackoff;
+        use tokio::time::sleep;
+
+        type Connection = Pin<Box<dyn Stream<Item = Result<&'static [u8], &'static str>>>>;
+
+        let open = |data: &'static [u8], open_for: Duration| -> Result<Connection, _> {
+            // Stays open until `open_for` after the start of the test, then ends
+            let end = stream::once(sleep(open_for)).filter_map(|()| future::ready(None));
+            Ok(Box::pin(stream::iter(vec![Ok(data)]).chain(end)))
+        };
+        let connections = vec![
+            open(b"data: a\n\n", Duration::from_secs(1)),
+            Err("1"),
+            open(b"data: b\n\n", Duration::from_secs(65)),
+            Err("2"),
+            Err("3"),
+            Err("4"),
+        ];
+        let connections = RefCell::new(connections.into_iter());
+
+        // A timer which only starts counting once it is first polled
+        let items = EventSource::new(
+            move |_| future::ready(connections.borrow_mut().next().unwrap()),
+            |delay| async move { sleep(delay).await },
+        )
+        .with_backoff(
+            ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1))
+                .max_attempts(3)
+                .reset_after(Duration::from_secs(60)),
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+        let reconnecting = |millis| Ok(SourceItem::Reconnecting(Duration::from_millis(millis)));
+        assert_eq!(
+            items,
+            vec![
+                Ok(SourceItem::Connecting),
+                Ok(SourceItem::Open),
+                Ok(message("a", "")),
+                reconnecting(100),
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport("1")),
+                reconnecting(200),
+                Ok(SourceItem::Connecting),
+                Ok(SourceItem::Open),
+                Ok(message("b", "")),
+                reconnecting(100),
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport("2")),
+                reconnecting(200),
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport("3")),
+                reconnecting(400),
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport("4")),
+                Ok(SourceItem::Closed(CloseReason::RetriesExhausted)),
+            ]
+        );
+    }
+
+    struct Recorder {
+        connections: std::vec::IntoIter<Connection>,
+        attempts: Vec<u32>,
+    }
+
+    impl Connector for &mut Recorder {
+        type Stream = stream::Iter<std::vec::IntoIter<Result<&'static [u8], &'static str>>>;
+        type Error = &'static str;
+        type Future = future::Ready<Result<Self::Stream, &'static str>>;
+
+        fn connect(&mut self, _last_event_id: Option<String>, attempt: u32) -> Self::Future {
+            self.attempts.push(attempt);
+            future::ready(self.connections.next().unwrap().map(stream::iter))
+        }
+
+        fn is_fatal(&self, err: &&'static str) -> bool {
+            *err == "fatal"
+        }
+    }
+
+    #[tokio::test]
+    async fn attempts() {
+        let connections: Vec<Connection> = vec![
+            Err("1"),
+            Err("2"),
+            Ok(vec![Ok(b"data: a\n\n")]),
+            Err("3"),
+            Err("fatal"),
+        ];
+        let mut recorder = Recorder {
+            connections: connections.into_iter(),
+            attempts: Vec::new(),
+        };
+        let items = EventSource::new(&mut recorder, |_| future::ready(()))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            items.last(),
+            Some(&Ok(SourceItem::Closed(CloseReason::Failed)))
+        );
+        assert_eq!(
+            items
+                .iter()
+                .filter(|item| matches!(item, Err(EventStreamError::Transport(_))))
+                .count(),
+            4
+        );
+        assert_eq!(recorder.attempts, vec![0, 1, 2, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn ready_state() {
+        let source = EventSource::new(
+            |_| future::ready(Ok::<_, ()>(stream::pending::<Result<&[u8], ()>>())),
+            |_| future::ready(()),
+        );
+        futures::pin_mut!(source);
+        assert_eq!(source.ready_state(), ReadyState::Connecting);
+
+        assert_eq!(source.next().await, Some(Ok(SourceItem::Connecting)));
+        assert_eq!(source.ready_state(), ReadyState::Connecting);
+        assert_eq!(source.next().await, Some(Ok(SourceItem::Open)));
+        assert_eq!(source.ready_state(), ReadyState::Open);
+
+        source.as_mut().close();
+        assert_eq!(source.ready_state(), ReadyState::Closed);
+        assert_eq!(
+            source.next().await,
+            Some(Ok(SourceItem::Closed(CloseReason::Requested)))
+        );
+        assert_eq!(source.next().await, None);
+    }
+}
@@ -1,118 +1,25 @@
 #[cfg(not(feature = "std"))]
-use alloc::string::{FromUtf8Error, String, ToString};
+use alloc::string::String;
 
-#[cfg(feature = "std")]
-use std::string::FromUtf8Error;
+#[cfg(feature = "bytes")]
+use crate::byte_str::ByteStr;
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "http")]
+use crate::response::ResponseError;
 
-use crate::event::Event;
-use crate::parser::{is_bom, is_lf, line, RawEventLine};
-use crate::utf8_stream::{Utf8Stream, Utf8StreamError};
+use crate::error::ParseError;
+use crate::event::{Event, Frame};
+use crate::event_parser::{EventParser, EventParserError, Options};
+use crate::limits::Limits;
+use crate::text::EventText;
 use core::fmt;
 use core::pin::Pin;
-use core::time::Duration;
 use futures_core::stream::Stream;
 use futures_core::task::{Context, Poll};
-use nom::error::Error as NomError;
 use pin_project_lite::pin_project;
 
-#[derive(Default, Debug)]
-struct EventBuilder {
-    event: Event,
-    is_complete: bool,
-}
-
-impl EventBuilder {
-    /// From the HTML spec
-    ///
-    /// -> If the field name is "event"
-    ///    Set the event type buffer to field value.
-    ///
-    /// -> If the field name is "data"
-    ///    Append the field value to the data buffer, then append a single U+000A LINE FEED (LF)
-    ///    character to the data buffer.
-    ///
-    /// -> If the field name is "id"
-    ///    If the field value does not contain U+0000 NULL, then set the last event ID buffer
-    ///    to the field value. Otherwise, ignore the field.
-    ///
-    /// -> If the field name is "retry"
-    ///    If the field value consists of only ASCII digits, then interpret the field value as
-    ///    an integer in base ten, and set the event stream's reconnection time to that integer.
-    ///    Otherwise, ignore the field.
-    ///
-    /// -> Otherwise
-    ///    The field is ignored.
-    fn add(&mut self, line: RawEventLine) {
-        match line {
-            RawEventLine::Field(field, val) => {
-                let val = val.unwrap_or("");
-                match field {
-                    "event" => {
-                        self.event.event = val.to_string();
-                    }
-                    "data" => {
-                        self.event.data.push_str(val);
-                        self.event.data.push('\u{000A}');
-                    }
-                    "id" => {
-                        if !val.contains('\u{0000}') {
-                            self.event.id = val.to_string()
-                        }
-                    }
-                    "retry" => {
-                        if let Ok(val) = val.parse::<u64>() {
-                            self.event.retry = Some(Duration::from_millis(val))
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            RawEventLine::Comment(_) => {}
-            RawEventLine::Empty => self.is_complete = true,
-        }
-    }
-
-    /// From the HTML spec
-    ///
-    /// 1. Set the last event ID string of the event source to the value of the last event ID
-    /// buffer. The buffer does not get reset, so the last event ID string of the event source
-    /// remains set to this value until the next time it is set by the server.
-    /// 2. If the data buffer is an empty string, set the data buffer and the event type buffer
-    /// to the empty string and return.
-    /// 3. If the data buffer's last character is a U+000A LINE FEED (LF) character, then remove
-    /// the last character from the data buffer.
-    /// 4. Let event be the result of creating an event using MessageEvent, in the relevant Realm
-    /// of the EventSource object.
-    /// 5. Initialize event's type attribute to message, its data attribute to data, its origin
-    /// attribute to the serialization of the origin of the event stream's final URL (i.e., the
-    /// URL after redirects), and its lastEventId attribute to the last event ID string of the
-    /// event source.
-    /// 6. If the event type buffer has a value other than the empty string, change the type of
-    /// the newly created event to equal the value of the event type buffer.
-    /// 7. Set the data buffer and the event type buffer to the empty string.
-    /// 8. Queue a task which, if the readyState attribute is set to a value other than CLOSED,
-    /// dispatches the newly created event at the EventSource object.
-    fn dispatch(&mut self) -> Option<Event> {
-        let builder = core::mem::take(self);
-        let mut event = builder.event;
-        self.event.id = event.id.clone();
-
-        if event.data.is_empty() {
-            return None;
-        }
-
-        if is_lf(event.data.chars().next_back().unwrap()) {
-            event.data.pop();
-        }
-
-        if event.event.is_empty() {
-            event.event = "message".to_string();
-        }
-
-        Some(event)
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub enum EventStreamState {
     NotStarted,
@@ -124,70 +31,131 @@ impl EventStreamState {
     fn is_terminated(self) -> bool {
         matches!(self, Self::Terminated)
     }
-    fn is_started(self) -> bool {
-        matches!(self, Self::Started)
-    }
 }
 
 pin_project! {
 /// A Stream of events
-pub struct EventStream<S> {
+///
+/// By default events are parsed into [`String`]s. An `EventStream<S, ByteStr>`, created with
+/// [`EventStream::new_shared`] or [`crate::SharedEventsource::eventsource_shared`], instead
+/// yields events whose fields share the memory of the source chunks.
+///
+/// An error does not end the stream. Content errors such as invalid lines or exceeded limits
+/// drop the event they occur in and the stream carries on with the next one, while
+/// [`EventStreamError::Transport`] errors are passed through from the source, which may or may
+/// not be able to continue.
+pub struct EventStream<S, T = String> {
     #[pin]
-    stream: Utf8Stream<S>,
-    buffer: String,
-    builder: EventBuilder,
+    stream: S,
+    parser: EventParser<T>,
     state: EventStreamState,
-    last_event_id: String,
 }
 }
 
 impl<S> EventStream<S> {
     /// Initialize the EventStream with a Stream
     pub fn new(stream: S) -> Self {
+        Self::with_parser(stream, EventParser::new())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<S> EventStream<S, ByteStr> {
+    /// Initialize a zero-copy EventStream with a Stream of chunks convertible into [`Bytes`]
+    pub fn new_shared(stream: S) -> Self {
+        Self::with_parser(stream, EventParser::default())
+    }
+}
+
+impl<S, T: EventText> EventStream<S, T> {
+    fn with_parser(stream: S, parser: EventParser<T>) -> Self {
         Self {
-            stream: Utf8Stream::new(stream),
-            buffer: String::new(),
-            builder: EventBuilder::default(),
+            stream,
+            parser,
             state: EventStreamState::NotStarted,
-            last_event_id: String::new(),
         }
     }
 
+    fn map_parser(mut self, f: impl FnOnce(EventParser<T>) -> EventParser<T>) -> Self {
+        self.parser = f(core::mem::take(&mut self.parser));
+        self
+    }
+
+    /// Collect fields other than `event`, `data`, `id` and `retry` into
+    /// [`Event::extensions`] instead of ignoring them
+    pub fn with_extensions(self) -> Self {
+        self.map_parser(EventParser::with_extensions)
+    }
+
+    /// Decode invalid UTF8 sequences to U+FFFD REPLACEMENT CHARACTER, as the HTML spec
+    /// requires, instead of failing with [`crate::ParseErrorKind::InvalidUtf8`]
+    pub fn with_lossy_utf8(self) -> Self {
+        self.map_parser(EventParser::with_lossy_utf8)
+    }
+
+    /// Enforce memory limits while parsing
+    pub fn with_limits(self, limits: Limits) -> Self {
+        self.map_parser(|parser| parser.with_limits(limits))
+    }
+
+    pub(crate) fn with_options(self, options: Options) -> Self {
+        self.map_parser(|parser| parser.with_options(options))
+    }
+
     /// Set the last event ID of the stream. Useful for initializing the stream with a previous
     /// last event ID
     pub fn set_last_event_id(&mut self, id: impl Into<String>) {
-        self.last_event_id = id.into();
+        self.parser.set_last_event_id(id);
     }
 
     /// Get the last event ID of the stream
     pub fn last_event_id(&self) -> &str {
-        &self.last_event_id
+        self.parser.last_event_id()
     }
 }
 
 /// Error thrown while parsing an event line
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum EventStreamError<E> {
-    /// Source stream is not valid UTF8
-    Utf8(FromUtf8Error),
     /// Source stream is not a valid EventStream
-    Parser(NomError<String>),
+    Parser(ParseError),
+    /// A line exceeded [`crate::Limits::max_line_length`], carrying the limit
+    LineTooLong(usize),
+    /// An event exceeded [`crate::Limits::max_event_size`], carrying the limit
+    EventTooLarge(usize),
+    /// More bytes were buffered than [`crate::Limits::max_buffer_size`] allows, carrying the
+    /// limit
+    BufferFull(usize),
     /// Underlying source stream error
     Transport(E),
+    /// The response to a connection attempt of an [`crate::EventSource`] does not start an
+    /// event stream, so the source won't reconnect
+    #[cfg(feature = "http")]
+    Response(ResponseError),
+}
+
+impl<E> EventStreamError<E> {
+    /// Whether the error came from the source stream rather than from its content
+    pub fn is_transport(&self) -> bool {
+        matches!(self, Self::Transport(_))
+    }
 }
 
-impl<E> From<Utf8StreamError<E>> for EventStreamError<E> {
-    fn from(err: Utf8StreamError<E>) -> Self {
+impl<E> From<EventParserError> for EventStreamError<E> {
+    fn from(err: EventParserError) -> Self {
         match err {
-            Utf8StreamError::Utf8(err) => Self::Utf8(err),
-            Utf8StreamError::Transport(err) => Self::Transport(err),
+            EventParserError::Parser(err) => Self::Parser(err),
+            EventParserError::LineTooLong(max) => Self::LineTooLong(max),
+            EventParserError::EventTooLarge(max) => Self::EventTooLarge(max),
+            EventParserError::BufferFull(max) => Self::BufferFull(max),
         }
     }
 }
 
-impl<E> From<NomError<&str>> for EventStreamError<E> {
-    fn from(err: NomError<&str>) -> Self {
-        EventStreamError::Parser(NomError::new(err.input.to_string(), err.code))
+impl<E> From<ParseError> for EventStreamError<E> {
+    fn from(err: ParseError) -> Self {
+        Self::Parser(err)
     }
 }
 
@@ -197,42 +165,101 @@ where
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Utf8(err) => f.write_fmt(format_args!("UTF8 error: {}", err)),
             Self::Parser(err) => f.write_fmt(format_args!("Parse error: {}", err)),
+            Self::LineTooLong(max) => {
+                f.write_fmt(format_args!("Line exceeds the limit of {} bytes", max))
+            }
+            Self::EventTooLarge(max) => {
+                f.write_fmt(format_args!("Event exceeds the limit of {} bytes", max))
+            }
+            Self::BufferFull(max) => {
+                f.write_fmt(format_args!("Buffer exceeds the limit of {} bytes", max))
+            }
             Self::Transport(err) => f.write_fmt(format_args!("Transport error: {}", err)),
+            #[cfg(feature = "http")]
+            Self::Response(err) => f.write_fmt(format_args!("Response error: {}", err)),
         }
     }
 }
 
 #[cfg(feature = "std")]
-impl<E> std::error::Error for EventStreamError<E> where E: fmt::Display + fmt::Debug + Send + Sync {}
-
-fn parse_event<E>(
-    buffer: &mut String,
-    builder: &mut EventBuilder,
-) -> Result<Option<Event>, EventStreamError<E>> {
-    if buffer.is_empty() {
-        return Ok(None);
-    }
-    loop {
-        match line(buffer.as_ref()) {
-            Ok((rem, next_line)) => {
-                builder.add(next_line);
-                let consumed = buffer.len() - rem.len();
-                let rem = buffer.split_off(consumed);
-                *buffer = rem;
-                if builder.is_complete {
-                    if let Some(event) = builder.dispatch() {
-                        return Ok(Some(event));
+impl<E> std::error::Error for EventStreamError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parser(err) => Some(err),
+            Self::Transport(err) => Some(err),
+            #[cfg(feature = "http")]
+            Self::Response(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<S, T: EventText> EventStream<S, T> {
+    /// Turn this into a stream which also yields comments and retry-only blocks
+    pub fn frames(self) -> FrameStream<S, T> {
+        FrameStream { inner: self }
+    }
+
+    /// Parse the next item, feeding the parser with `poll_feed` until one is complete.
+    /// `poll_feed` returns `None` once the source has ended.
+    pub(crate) fn poll_parser<E, I>(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        poll_feed: impl Fn(
+            Pin<&mut S>,
+            &mut EventParser<T>,
+            &mut Context,
+        ) -> Poll<Option<Result<(), E>>>,
+        next: impl Fn(&mut EventParser<T>) -> Result<Option<I>, EventParserError>,
+    ) -> Poll<Option<Result<I, EventStreamError<E>>>> {
+        let mut this = self.project();
+
+        loop {
+            match next(this.parser) {
+                Ok(Some(item)) => return Poll::Ready(Some(Ok(item))),
+                Err(err) => return Poll::Ready(Some(Err(err.into()))),
+                Ok(None) => {}
+            }
+
+            if this.state.is_terminated() {
+                return Poll::Ready(None);
+            }
+
+            match poll_feed(this.stream.as_mut(), this.parser, cx) {
+                Poll::Ready(Some(Ok(()))) => *this.state = EventStreamState::Started,
+                Poll::Ready(Some(Err(err))) => {
+                    return Poll::Ready(Some(Err(EventStreamError::Transport(err))))
+                }
+                Poll::Ready(None) => {
+                    *this.state = EventStreamState::Terminated;
+                    if let Err(err) = this.parser.finish() {
+                        return Poll::Ready(Some(Err(err.into())));
                     }
                 }
+                Poll::Pending => return Poll::Pending,
             }
-            Err(nom::Err::Incomplete(_)) => return Ok(None),
-            Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => return Err(err.into()),
         }
     }
 }
 
+/// Poll the next chunk of a stream of bytes and `feed` it to the parser
+fn poll_chunk<S, B, E>(
+    stream: Pin<&mut S>,
+    cx: &mut Context,
+    feed: impl FnOnce(B),
+) -> Poll<Option<Result<(), E>>>
+where
+    S: Stream<Item = Result<B, E>>,
+{
+    stream
+        .poll_next(cx)
+        .map(|item| item.map(|result| result.map(feed)))
+}
+
 impl<S, B, E> Stream for EventStream<S>
 where
     S: Stream<Item = Result<B, E>>,
@@ -241,63 +268,93 @@ where
     type Item = Result<Event, EventStreamError<E>>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        let mut this = self.project();
+        self.poll_parser(
+            cx,
+            |stream, parser, cx| poll_chunk(stream, cx, |bytes: B| parser.feed(bytes.as_ref())),
+            EventParser::next_event,
+        )
+    }
+}
 
-        match parse_event(this.buffer, this.builder) {
-            Ok(Some(event)) => {
-                *this.last_event_id = event.id.clone();
-                return Poll::Ready(Some(Ok(event)));
-            }
-            Err(err) => return Poll::Ready(Some(Err(err))),
-            _ => {}
-        }
+#[cfg(feature = "bytes")]
+impl<S, B, E> Stream for EventStream<S, ByteStr>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Into<Bytes>,
+{
+    type Item = Result<Event<ByteStr>, EventStreamError<E>>;
 
-        if this.state.is_terminated() {
-            return Poll::Ready(None);
-        }
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_parser(
+            cx,
+            |stream, parser, cx| poll_chunk(stream, cx, |bytes: B| parser.feed_bytes(bytes.into())),
+            EventParser::next_event,
+        )
+    }
+}
 
-        loop {
-            match this.stream.as_mut().poll_next(cx) {
-                Poll::Ready(Some(Ok(string))) => {
-                    if string.is_empty() {
-                        continue;
-                    }
+pin_project! {
+/// A Stream of events, comments and retry-only blocks, created with [`EventStream::frames`]
+pub struct FrameStream<S, T = String> {
+    #[pin]
+    inner: EventStream<S, T>,
+}
+}
 
-                    let slice = if this.state.is_started() {
-                        &string
-                    } else {
-                        *this.state = EventStreamState::Started;
-                        if is_bom(string.chars().next().unwrap()) {
-                            &string[1..]
-                        } else {
-                            &string
-                        }
-                    };
-                    this.buffer.push_str(slice);
-
-                    match parse_event(this.buffer, this.builder) {
-                        Ok(Some(event)) => {
-                            *this.last_event_id = event.id.clone();
-                            return Poll::Ready(Some(Ok(event)));
-                        }
-                        Err(err) => return Poll::Ready(Some(Err(err))),
-                        _ => {}
-                    }
-                }
-                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
-                Poll::Ready(None) => {
-                    *this.state = EventStreamState::Terminated;
-                    return Poll::Ready(None);
-                }
-                Poll::Pending => return Poll::Pending,
-            }
-        }
+impl<S, T: EventText> FrameStream<S, T> {
+    pub(crate) fn inner(self: Pin<&mut Self>) -> Pin<&mut EventStream<S, T>> {
+        self.project().inner
+    }
+
+    /// Set the last event ID of the stream. Useful for initializing the stream with a previous
+    /// last event ID
+    pub fn set_last_event_id(&mut self, id: impl Into<String>) {
+        self.inner.set_last_event_id(id);
+    }
+
+    /// Get the last event ID of the stream
+    pub fn last_event_id(&self) -> &str {
+        self.inner.last_event_id()
+    }
+}
+
+impl<S, B, E> Stream for FrameStream<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    type Item = Result<Frame, EventStreamError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner().poll_parser(
+            cx,
+            |stream, parser, cx| poll_chunk(stream, cx, |bytes: B| parser.feed(bytes.as_ref())),
+            EventParser::next_frame,
+        )
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<S, B, E> Stream for FrameStream<S, ByteStr>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Into<Bytes>,
+{
+    type Item = Result<Frame<ByteStr>, EventStreamError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner().poll_parser(
+            cx,
+            |stream, parser, cx| poll_chunk(stream, cx, |bytes: B| parser.feed_bytes(bytes.into())),
+            EventParser::next_frame,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::time::Duration;
     use futures::prelude::*;
 
     #[tokio::test]
@@ -560,4 +617,122 @@ data: test
             ]
         );
     }
+
+    #[tokio::test]
+    async fn frames() {
+        assert_eq!(
+            EventStream::new(futures::stream::iter(vec![
+                Ok::<_, ()>(": keep-alive\n\nretry: 10"),
+                Ok::<_, ()>("00\n\ndata: Hello, world!\n\n")
+            ]))
+            .frames()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap(),
+            vec![
+                Frame::Comment(" keep-alive".to_string()),
+                Frame::Retry(Duration::from_secs(1)),
+                Frame::Event(Event {
+                    event: "message".to_string(),
+                    data: "Hello, world!".to_string(),
+                    ..Default::default()
+                })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn extensions() {
+        let events = EventStream::new(futures::stream::iter(vec![Ok::<_, ()>(
+            "channel: news\ndata: Hello, world!\n\n",
+        )]))
+        .with_extensions()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].extensions.get("channel").unwrap(), "news");
+    }
+
+    #[tokio::test]
+    async fn recovery() {
+        let results = EventStream::new(futures::stream::iter(vec![
+            Ok(b"data: first\n\ndata: bro".to_vec()),
+            Ok(b"ken\xff\ndata: dropped\n\n".to_vec()),
+            Err(()),
+            Ok(b"data: last\n\n".to_vec()),
+        ]))
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().data, "first");
+        assert!(!results[1].as_ref().unwrap_err().is_transport());
+        assert!(results[2].as_ref().unwrap_err().is_transport());
+        assert_eq!(results[3].as_ref().unwrap().data, "last");
+    }
+
+    #[tokio::test]
+    async fn lossy_utf8() {
+        let input = || {
+            futures::stream::iter(vec![
+                Ok::<_, ()>(b"data: \xff\n\n".to_vec()),
+                Ok::<_, ()>(b"data: next\n\n".to_vec()),
+            ])
+        };
+
+        let results = EventStream::new(input()).collect::<Vec<_>>().await;
+        assert!(matches!(results[0], Err(EventStreamError::Parser(_))));
+
+        let events = EventStream::new(input())
+            .with_lossy_utf8()
+            .map_ok(|event| event.data)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(events, vec!["\u{fffd}", "next"]);
+    }
+
+    #[tokio::test]
+    async fn limits() {
+        let results = EventStream::new(futures::stream::iter(vec![
+            Ok::<_, ()>("data: Hello, "),
+            Ok::<_, ()>("world!\n\ndata: ok\n\n"),
+        ]))
+        .with_limits(crate::Limits::new().max_line_length(16))
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(
+            results,
+            vec![
+                Err(EventStreamError::LineTooLong(16)),
+                Ok(Event {
+                    event: "message".to_string(),
+                    data: "ok".to_string(),
+                    ..Default::default()
+                })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn error_sources() {
+        use std::error::Error;
+
+        let results = EventStream::new(futures::stream::iter(vec![
+            Ok(b"data: \xff\n\n".to_vec()),
+            Err(std::io::Error::other("reset")),
+        ]))
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(results.len(), 2);
+
+        let err = results[0].as_ref().unwrap_err();
+        assert!(matches!(err, EventStreamError::Parser(_)));
+        let source = err.source().unwrap();
+        assert!(source.is::<ParseError>());
+        assert!(source.source().unwrap().is::<core::str::Utf8Error>());
+
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.source().unwrap().to_string(), "reset");
+    }
 }
@@ -0,0 +1,87 @@
+#[cfg(feature = "actix-web")]
+use crate::encoder::lines;
+use crate::event::Frame;
+use crate::event_parser::EventParser;
+use bytes::Bytes;
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use futures_core::stream::Stream;
+
+/// Error converting a framework's own event type into a [`Frame`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NativeEventError {
+    /// The framework did not encode the event right away, or rejected it
+    Unencoded,
+    /// The event holds several frames, such as a comment written next to its data or a
+    /// comment of several lines, which a single [`Frame`] can't carry
+    MultipleFrames,
+}
+
+impl fmt::Display for NativeEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unencoded => f.write_str("event was not encoded"),
+            Self::MultipleFrames => f.write_str("event holds several frames"),
+        }
+    }
+}
+
+impl std::error::Error for NativeEventError {}
+
+/// A Stream of a single item, used to encode one event with a framework's own encoder
+pub(crate) struct Once<T>(pub(crate) Option<T>);
+
+impl<T: Unpin> Stream for Once<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<T>> {
+        Poll::Ready(self.0.take())
+    }
+}
+
+/// Encode a single event by polling a framework body of a [`Once`] stream
+///
+/// The stream is always ready, so the first poll yields the encoded event unless the
+/// framework waits or rejects it.
+pub(crate) fn render<E>(
+    mut poll_next: impl FnMut(&mut Context) -> Poll<Option<Result<Bytes, E>>>,
+) -> Result<Bytes, NativeEventError> {
+    match poll_next(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(Some(Ok(bytes))) => Ok(bytes),
+        _ => Err(NativeEventError::Unencoded),
+    }
+}
+
+/// The frame of a framework comment, which is written as `: ` followed by the comment
+///
+/// Like any parsed comment its text is everything after the colon, so it keeps the leading
+/// space, the same as [`parse_frame`] gives for a comment. Every line is written as a
+/// comment of its own, so a comment of several lines can't be converted.
+#[cfg(feature = "actix-web")]
+pub(crate) fn comment_frame(comment: &str) -> Result<Frame, NativeEventError> {
+    let mut lines = lines(comment);
+    match (lines.next(), lines.next()) {
+        (Some(line), None) => Ok(Frame::Comment(format!(" {}", line))),
+        _ => Err(NativeEventError::MultipleFrames),
+    }
+}
+
+/// Parse an encoded framework event into a [`Frame`]
+///
+/// Without any fields it is an empty comment, like a keep-alive.
+pub(crate) fn parse_frame(bytes: &[u8]) -> Result<Frame, NativeEventError> {
+    let mut parser = EventParser::new().with_lossy_utf8();
+    parser.feed(bytes);
+    // The end of the stream also ends a partial event, which is otherwise discarded
+    parser.feed(b"\n\n");
+    let _ = parser.finish();
+    let mut frame = None;
+    while let Ok(Some(next)) = parser.next_frame() {
+        if frame.replace(next).is_some() {
+            return Err(NativeEventError::MultipleFrames);
+        }
+    }
+    Ok(frame.unwrap_or_else(|| Frame::Comment(String::new())))
+}
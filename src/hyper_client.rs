@@ -0,0 +1,168 @@
+use crate::byte_str::ByteStr;
+use crate::event_stream::EventStream;
+use crate::response::{validate, ResponseError, MAX_BODY_SNIPPET};
+use bytes::{Buf, Bytes};
+use core::pin::Pin;
+use futures_core::ready;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use http::Response;
+use http_body::Body;
+use pin_project_lite::pin_project;
+
+pin_project! {
+/// A Stream of the data frames of an [`http_body::Body`], such as hyper's `Incoming`
+///
+/// Trailers are skipped, and data frames are turned into [`Bytes`] without copying them when
+/// they already are.
+#[derive(Debug)]
+pub struct BodyStream<B> {
+    #[pin]
+    body: B,
+}
+}
+
+impl<B> BodyStream<B> {
+    /// Create a stream of the data frames of `body`
+    pub fn new(body: B) -> Self {
+        Self { body }
+    }
+
+    /// Unwrap the underlying body
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+impl<B: Body> Stream for BodyStream<B> {
+    type Item = Result<Bytes, B::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut body = self.project().body;
+        loop {
+            let frame = match ready!(body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            };
+            if let Ok(mut data) = frame.into_data() {
+                let len = data.remaining();
+                return Poll::Ready(Some(Ok(data.copy_to_bytes(len))));
+            }
+        }
+    }
+}
+
+/// Turn a response into an [`EventStream`] of its body, once it was checked to start an event
+/// stream with [`crate::validate_response`]
+///
+/// The events share the memory of the body's frames. If the response is rejected, the start
+/// of its body is read into the [`ResponseError`]. Requests should be prepared with
+/// [`crate::prepare_request`] to ask for an event stream.
+///
+/// ```no_run
+/// use bytes::Bytes;
+/// use eventsource_stream::{prepare_request, response_events};
+/// use futures::prelude::*;
+/// use http_body_util::Empty;
+///
+/// # async fn run(response: http::Response<Empty<Bytes>>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut request = http::Request::get("http://localhost:7020/notifications")
+///     .body(Empty::<Bytes>::new())?;
+/// prepare_request(&mut request, None);
+/// // let response = sender.send_request(request).await?;
+///
+/// let mut events = response_events(response).await?;
+/// while let Some(event) = events.next().await {
+///     println!("{}", event?.data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn response_events<B: Body>(
+    response: Response<B>,
+) -> Result<EventStream<BodyStream<B>, ByteStr>, ResponseError> {
+    let (parts, body) = response.into_parts();
+    let body = BodyStream::new(body);
+    if let Err(err) = validate(parts.status, &parts.headers) {
+        let mut snippet = Vec::new();
+        let mut body = core::pin::pin!(body);
+        while snippet.len() < MAX_BODY_SNIPPET {
+            match core::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                Some(Ok(chunk)) => snippet.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        return Err(err.with_body(&snippet));
+    }
+    Ok(EventStream::new_shared(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResponseErrorKind;
+    use futures::prelude::*;
+    use http::{HeaderMap, StatusCode};
+    use http_body::Frame;
+    use http_body_util::{Full, StreamBody};
+
+    fn response<B>(status: u16, content_type: &str, body: B) -> Response<B> {
+        Response::builder()
+            .status(status)
+            .header("Content-Type", content_type)
+            .body(body)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn body_events() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("x-checksum", "abc".parse().unwrap());
+        let frames = vec![
+            Ok::<_, std::io::Error>(Frame::data(Bytes::from_static(b"id: 1\ndata: fir"))),
+            Ok(Frame::data(Bytes::from_static(b"st\n\ndata: second\n\n"))),
+            Ok(Frame::trailers(trailers)),
+        ];
+        let body = StreamBody::new(stream::iter(frames));
+
+        let events = response_events(response(200, "text/event-stream", body))
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data, "first");
+        assert_eq!(events[1].data, "second");
+        assert_eq!(events[1].id, "1");
+    }
+
+    #[tokio::test]
+    async fn shares_frames() {
+        let frame = Bytes::from_static(b"data: shared\n\n");
+        let mut chunks = BodyStream::new(Full::new(frame.clone()));
+        let chunk = chunks.next().await.unwrap().unwrap();
+        assert_eq!(chunk.as_ptr(), frame.as_ptr());
+        assert!(chunks.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejected_response() {
+        let body = Full::new(Bytes::from_static(b"{\"error\": \"unauthorized\"}"));
+        let err = response_events(response(401, "application/json", body))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ResponseErrorKind::InvalidStatus);
+        assert_eq!(err.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(err.body(), "{\"error\": \"unauthorized\"}");
+
+        let body = Full::new(Bytes::from_static(b"<html></html>"));
+        let err = response_events(response(200, "text/html", body))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ResponseErrorKind::InvalidContentType);
+    }
+}
@@ -24,18 +24,117 @@
 //!     }
 //! }
 //! ```
+//!
+//! The parsing itself is done by a sans-IO [`EventParser`], which can also be driven directly
+//! from blocking sockets, callbacks or any other source of bytes. In the other direction an
+//! [`EventEncoder`] serializes events into the wire format.
+//!
+//! # Features
+//!
+//! - `std` (default): implement `std::error::Error` for the error types and read events from a
+//!   `std::io::BufRead` with the blocking `EventIter`. Without it the crate only needs `alloc`.
+//! - `actix-web`: `Responder` for `EncodeStream`, and conversions between `Event` and
+//!   actix-web-lab's `sse::Event`.
+//! - `axum`: `IntoResponse` for `EncodeStream`, and conversions between `Event` and axum's
+//!   `sse::Event`.
+//! - `bytes`: zero-copy events backed by `bytes::Bytes` and streams of encoded bytes. Together
+//!   with `std` also a `ReplayBuffer` replaying missed events to reconnecting clients.
+//! - `futures-io`: `EventStream::from_reader` to parse any `futures::io::AsyncBufRead`.
+//! - `tokio`: a `TokioTimer` for the reconnecting `EventSource`.
+//! - `http`: preparation of `http::Request`s and validation of `http::Response`s as required
+//!   by the spec, and `event_stream_response` for servers.
+//! - `hyper`: an `EventStream` of any `http_body::Body`, such as a hyper 1 `Incoming` response,
+//!   through `response_events`, and an `http_body::Body` implementation for `EncodeStream`.
+//! - `reqwest`: a ready-made `EventSource` for reqwest requests through `RequestBuilderExt`.
+//! - `tokio-util`: an `EventCodec` to read and write events with `FramedRead` and `FramedWrite`.
+//! - `tower`: an `EventLayer` for middleware mapping, filtering or inserting the events of
+//!   event stream responses.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+#[cfg(feature = "actix-web")]
+mod actix_server;
+#[cfg(feature = "futures-io")]
+mod async_read;
+#[cfg(feature = "axum")]
+mod axum_server;
+mod backoff;
+#[cfg(feature = "bytes")]
+mod byte_str;
+#[cfg(feature = "tokio-util")]
+mod codec;
+#[cfg(feature = "bytes")]
+mod encode_stream;
+mod encoder;
+mod error;
 mod event;
+#[cfg(feature = "std")]
+mod event_iter;
+mod event_parser;
+mod event_source;
 mod event_stream;
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+mod framework;
+#[cfg(feature = "hyper")]
+mod hyper_client;
+mod limits;
 mod parser;
+#[cfg(all(feature = "bytes", feature = "std"))]
+mod replay;
+#[cfg(feature = "reqwest")]
+mod reqwest_client;
+#[cfg(feature = "http")]
+mod response;
+#[cfg(test)]
+mod test_util;
+mod text;
+mod timer;
+#[cfg(feature = "tower")]
+mod tower_middleware;
 mod traits;
-mod utf8_stream;
 
-pub use event::Event;
-pub use event_stream::{EventStream, EventStreamError};
+#[cfg(feature = "futures-io")]
+pub use async_read::AsyncBufReadSource;
+pub use backoff::{BackoffPolicy, ConstantBackoff, ExponentialBackoff, ServerRetry};
+#[cfg(feature = "bytes")]
+pub use byte_str::ByteStr;
+#[cfg(feature = "tokio-util")]
+pub use codec::EventCodec;
+#[cfg(feature = "bytes")]
+pub use encode_stream::{EncodeStream, KeepAlive};
+pub use encoder::{EncodeError, EventEncoder};
+pub use error::{ParseError, ParseErrorKind};
+pub use event::{Event, Extensions, Frame};
+#[cfg(feature = "std")]
+pub use event_iter::EventIter;
+pub use event_parser::{EventParser, EventParserError};
+pub use event_source::{CloseReason, Connector, EventSource, ReadyState, SourceItem};
+pub use event_stream::{EventStream, EventStreamError, FrameStream};
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+pub use framework::NativeEventError;
+#[cfg(feature = "hyper")]
+pub use hyper_client::{response_events, BodyStream};
+pub use limits::{LimitAction, Limits};
+#[cfg(all(feature = "bytes", feature = "std"))]
+pub use replay::{Evicted, Replay, ReplayBuffer};
+#[cfg(feature = "reqwest")]
+pub use reqwest_client::{
+    RequestBuilderExt, ReqwestConnector, ReqwestError, ReqwestEventSource, ResponseStream,
+};
+#[cfg(feature = "http")]
+pub use response::{
+    event_stream_response, prepare_request, validate_response, ResponseError, ResponseErrorKind,
+    MAX_BODY_SNIPPET,
+};
+pub use text::EventText;
+#[cfg(feature = "tokio")]
+pub use timer::TokioTimer;
+pub use timer::{NoTimer, Timer};
+#[cfg(feature = "tower")]
+pub use tower_middleware::{EventBody, EventBodyError, EventFuture, EventLayer, EventService};
 pub use traits::Eventsource;
+#[cfg(feature = "bytes")]
+pub use traits::{EncodeEventsource, SharedEventsource};
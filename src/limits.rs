@@ -0,0 +1,68 @@
+/// What an [`crate::EventParser`] does when one of its [`Limits`] is exceeded
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitAction {
+    /// Discard the offending event and report an error. Parsing resumes after the event.
+    #[default]
+    Error,
+    /// Silently discard the offending event and resume parsing after it
+    Discard,
+}
+
+/// Memory limits of an [`crate::EventParser`]
+///
+/// No limits are enforced by default. Once a limit is exceeded the event being parsed is
+/// discarded up to the blank line which ends it, and the [`LimitAction`] decides whether this is
+/// reported as an error.
+///
+/// ```
+/// use eventsource_stream::{EventParser, Limits};
+///
+/// let parser = EventParser::new().with_limits(
+///     Limits::new()
+///         .max_line_length(64 * 1024)
+///         .max_event_size(1024 * 1024),
+/// );
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) max_event_size: Option<usize>,
+    pub(crate) max_buffer_size: Option<usize>,
+    pub(crate) action: LimitAction,
+}
+
+impl Limits {
+    /// Create limits which don't restrict anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum length of a single line in bytes, excluding its end-of-line
+    pub fn max_line_length(mut self, bytes: usize) -> Self {
+        self.max_line_length = Some(bytes);
+        self
+    }
+
+    /// Maximum size of a single event in bytes, counting the values of its `event`, `data` and
+    /// `id` fields, the line breaks joining its data and the names and values of its
+    /// extensions
+    pub fn max_event_size(mut self, bytes: usize) -> Self {
+        self.max_event_size = Some(bytes);
+        self
+    }
+
+    /// Maximum number of bytes buffered by the parser which have not been parsed yet, counting
+    /// the chunk being fed. A chunk which exceeds it is dropped together with the unparsed bytes
+    /// and the event in progress, and parsing resumes once an event ends. Chunks are never
+    /// split, so the limit has to be larger than the chunks of the source.
+    pub fn max_buffer_size(mut self, bytes: usize) -> Self {
+        self.max_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Choose what happens when a limit is exceeded
+    pub fn on_exceeded(mut self, action: LimitAction) -> Self {
+        self.action = action;
+        self
+    }
+}
@@ -1,7 +1,7 @@
 use nom::branch::alt;
-use nom::bytes::streaming::{tag, take_while, take_while1, take_while_m_n};
-use nom::combinator::opt;
-use nom::sequence::{preceded, terminated, tuple};
+use nom::bytes::complete::{take_while, take_while1, take_while_m_n};
+use nom::combinator::{eof, opt};
+use nom::sequence::{preceded, tuple};
 use nom::IResult;
 
 /// ; ABNF definition from HTML spec
@@ -22,6 +22,9 @@ use nom::IResult;
 ///                 ; a scalar value other than U+000A LINE FEED (LF), U+000D CARRIAGE RETURN (CR), or U+003A COLON (:)
 /// any-char      = %x0000-0009 / %x000B-000C / %x000E-10FFFF
 ///                 ; a scalar value other than U+000A LINE FEED (LF) or U+000D CARRIAGE RETURN (CR)
+///
+/// ; Lines are split on end-of-line by the caller, so the parsers below only see the contents
+/// ; of a single line without its terminator.
 
 #[derive(Debug)]
 pub enum RawEventLine<'a> {
@@ -30,16 +33,6 @@ pub enum RawEventLine<'a> {
     Empty,
 }
 
-#[inline]
-pub fn is_lf(c: char) -> bool {
-    c == '\u{000A}'
-}
-
-#[inline]
-pub fn is_cr(c: char) -> bool {
-    c == '\u{000D}'
-}
-
 #[inline]
 pub fn is_space(c: char) -> bool {
     c == '\u{0020}'
@@ -50,11 +43,6 @@ pub fn is_colon(c: char) -> bool {
     c == '\u{003A}'
 }
 
-#[inline]
-pub fn is_bom(c: char) -> bool {
-    c == '\u{feff}'
-}
-
 #[inline]
 pub fn is_name_char(c: char) -> bool {
     matches!(c, '\u{0000}'..='\u{0009}'
@@ -71,48 +59,29 @@ pub fn is_any_char(c: char) -> bool {
 }
 
 #[inline]
-fn crlf(input: &str) -> IResult<&str, &str> {
-    tag("\u{000D}\u{000A}")(input)
-}
-
-#[inline]
-fn end_of_line(input: &str) -> IResult<&str, &str> {
-    alt((
-        crlf,
-        take_while_m_n(1, 1, is_cr),
-        take_while_m_n(1, 1, is_lf),
-    ))(input)
+fn comment(input: &str) -> IResult<&str, RawEventLine<'_>> {
+    preceded(take_while_m_n(1, 1, is_colon), take_while(is_any_char))(input)
+        .map(|(input, comment)| (input, RawEventLine::Comment(comment)))
 }
 
 #[inline]
-fn comment(input: &str) -> IResult<&str, RawEventLine> {
-    preceded(
-        take_while_m_n(1, 1, is_colon),
-        terminated(take_while(is_any_char), end_of_line),
-    )(input)
-    .map(|(input, comment)| (input, RawEventLine::Comment(comment)))
-}
-
-#[inline]
-fn field(input: &str) -> IResult<&str, RawEventLine> {
-    terminated(
-        tuple((
-            take_while1(is_name_char),
-            opt(preceded(
-                take_while_m_n(1, 1, is_colon),
-                preceded(opt(take_while_m_n(1, 1, is_space)), take_while(is_any_char)),
-            )),
+fn field(input: &str) -> IResult<&str, RawEventLine<'_>> {
+    tuple((
+        take_while1(is_name_char),
+        opt(preceded(
+            take_while_m_n(1, 1, is_colon),
+            preceded(opt(take_while_m_n(1, 1, is_space)), take_while(is_any_char)),
         )),
-        end_of_line,
-    )(input)
+    ))(input)
     .map(|(input, (field, data))| (input, RawEventLine::Field(field, data)))
 }
 
 #[inline]
-fn empty(input: &str) -> IResult<&str, RawEventLine> {
-    end_of_line(input).map(|(i, _)| (i, RawEventLine::Empty))
+fn empty(input: &str) -> IResult<&str, RawEventLine<'_>> {
+    eof(input).map(|(i, _)| (i, RawEventLine::Empty))
 }
 
-pub fn line(input: &str) -> IResult<&str, RawEventLine> {
+/// Parse the contents of a single line, excluding its end-of-line
+pub fn line(input: &str) -> IResult<&str, RawEventLine<'_>> {
     alt((comment, field, empty))(input)
 }
@@ -0,0 +1,366 @@
+use crate::encoder::{EncodeError, EventEncoder};
+use crate::event::Event;
+use bytes::Bytes;
+use core::fmt;
+use core::pin::Pin;
+use core::time::Duration;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll, Waker};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+struct Entry {
+    id: String,
+    bytes: Bytes,
+    sent_at: Instant,
+}
+
+struct Shared {
+    entries: VecDeque<Entry>,
+    /// Sequence number of the first entry
+    first: u64,
+    size: usize,
+    wakers: HashMap<u64, Waker>,
+    next_subscriber: u64,
+}
+
+impl Shared {
+    fn end(&self) -> u64 {
+        self.first + self.entries.len() as u64
+    }
+
+    fn evict_front(&mut self) {
+        if let Some(entry) = self.entries.pop_front() {
+            self.first += 1;
+            self.size -= entry.bytes.len();
+        }
+    }
+}
+
+/// An in-memory buffer of recently sent events, replaying them to clients which reconnect with
+/// a `Last-Event-ID`
+///
+/// Events are encoded once when they are sent and shared by all subscribers. The buffer holds
+/// at most `max_events` events, and optionally a maximum number of encoded bytes and a maximum
+/// age, evicting the oldest events first. The newest event is always kept. A subscriber which
+/// falls so far behind that its next event was evicted ends, so that its client reconnects and
+/// resumes from where it left off.
+///
+/// Each event is encoded on its own, so every event with an id carries its `id` line. Events
+/// without an id are replayed too, but can't be resumed after.
+///
+/// ```
+/// use eventsource_stream::{Event, ReplayBuffer};
+/// use futures::prelude::*;
+///
+/// # futures::executor::block_on(async {
+/// let buffer = ReplayBuffer::new(1000);
+/// for id in 1..=3 {
+///     buffer
+///         .send(&Event {
+///             data: format!("event {}", id),
+///             id: id.to_string(),
+///             ..Default::default()
+///         })
+///         .unwrap();
+/// }
+///
+/// // A client reconnecting with `Last-Event-ID: 2`
+/// let replay = buffer.subscribe(Some("2")).unwrap_or_else(|evicted| {
+///     // The events since are gone, send a snapshot instead
+///     evicted.into_live()
+/// });
+/// let chunks = replay.take(1).collect::<Vec<_>>().await;
+/// assert_eq!(chunks, vec!["id: 3\ndata: event 3\n\n"]);
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct ReplayBuffer {
+    shared: Arc<Mutex<Shared>>,
+    max_events: usize,
+    max_bytes: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+impl ReplayBuffer {
+    /// Create a buffer holding up to `max_events` events
+    pub fn new(max_events: usize) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                entries: VecDeque::new(),
+                first: 0,
+                size: 0,
+                wakers: HashMap::new(),
+                next_subscriber: 0,
+            })),
+            max_events,
+            max_bytes: None,
+            max_age: None,
+        }
+    }
+
+    /// Also limit the total size of the encoded events in bytes
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Also evict events once they are older than `max_age`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn evict(&self, shared: &mut Shared) {
+        let now = Instant::now();
+        while shared.entries.len() > 1 {
+            let front = &shared.entries[0];
+            let too_many = shared.entries.len() > self.max_events;
+            let too_large = self.max_bytes.is_some_and(|max| shared.size > max);
+            let too_old = self
+                .max_age
+                .is_some_and(|max| now.duration_since(front.sent_at) > max);
+            if !(too_many || too_large || too_old) {
+                break;
+            }
+            shared.evict_front();
+        }
+    }
+
+    /// Encode an event, store it and send it to all subscribers
+    pub fn send<T: AsRef<str>>(&self, event: &Event<T>) -> Result<(), EncodeError> {
+        let bytes = Bytes::from(EventEncoder::new().encode(event)?);
+        let mut shared = self.lock();
+        shared.size += bytes.len();
+        shared.entries.push_back(Entry {
+            id: event.id.as_ref().to_string(),
+            bytes,
+            sent_at: Instant::now(),
+        });
+        self.evict(&mut shared);
+        let wakers = shared
+            .wakers
+            .drain()
+            .map(|(_, waker)| waker)
+            .collect::<Vec<_>>();
+        // Woken subscribers may be polled right away, which takes the lock
+        drop(shared);
+        for waker in wakers {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Subscribe to the events sent after the event with `last_event_id`, followed by all
+    /// events sent from now on
+    ///
+    /// Without a last event ID only new events are sent. If the event is no longer buffered,
+    /// or was never sent, [`Evicted`] is returned, from which a subscription to new events can
+    /// be taken after catching up the client in another way.
+    pub fn subscribe(&self, last_event_id: Option<&str>) -> Result<Replay, Evicted> {
+        let mut shared = self.lock();
+        self.evict(&mut shared);
+        let live = shared.end();
+        let next = match last_event_id.filter(|id| !id.is_empty()) {
+            Some(id) => shared
+                .entries
+                .iter()
+                .rposition(|entry| entry.id == id)
+                .map(|position| shared.first + position as u64 + 1),
+            None => Some(live),
+        };
+        let id = shared.next_subscriber;
+        shared.next_subscriber += 1;
+        let replay = Replay {
+            shared: self.shared.clone(),
+            id,
+            next: next.unwrap_or(live),
+        };
+        match next {
+            Some(_) => Ok(replay),
+            None => Err(Evicted(replay)),
+        }
+    }
+}
+
+impl fmt::Debug for ReplayBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplayBuffer")
+            .field("max_events", &self.max_events)
+            .field("max_bytes", &self.max_bytes)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A Stream of the encoded events of a [`ReplayBuffer`], created with
+/// [`ReplayBuffer::subscribe`]
+///
+/// It yields the buffered events first and then waits for new ones. It ends once its next event
+/// was evicted before it could be sent.
+pub struct Replay {
+    shared: Arc<Mutex<Shared>>,
+    id: u64,
+    next: u64,
+}
+
+impl fmt::Debug for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay").finish_non_exhaustive()
+    }
+}
+
+impl Stream for Replay {
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Bytes>> {
+        let this = &mut *self;
+        let mut shared = this.shared.lock().unwrap_or_else(|err| err.into_inner());
+        if this.next < shared.first {
+            return Poll::Ready(None);
+        }
+        let index = (this.next - shared.first) as usize;
+        if let Some(entry) = shared.entries.get(index) {
+            let bytes = entry.bytes.clone();
+            this.next += 1;
+            return Poll::Ready(Some(bytes));
+        }
+        shared.wakers.insert(this.id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Sent in data frames like the `http_body::Body` of a [`crate::EncodeStream`]
+#[cfg(feature = "hyper")]
+impl http_body::Body for Replay {
+    type Data = Bytes;
+    type Error = core::convert::Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<http_body::Frame<Bytes>, Self::Error>>> {
+        self.poll_next(cx)
+            .map(|bytes| bytes.map(|bytes| Ok(http_body::Frame::data(bytes))))
+    }
+}
+
+impl Drop for Replay {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        shared.wakers.remove(&self.id);
+    }
+}
+
+/// Error of [`ReplayBuffer::subscribe`] when the last event ID is not buffered, so the events
+/// sent since can't be replayed
+#[derive(Debug)]
+pub struct Evicted(Replay);
+
+impl Evicted {
+    /// Take a subscription to the events sent from now on
+    pub fn into_live(self) -> Replay {
+        self.0
+    }
+}
+
+impl fmt::Display for Evicted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Last event ID is no longer buffered")
+    }
+}
+
+impl std::error::Error for Evicted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::message;
+    use futures::prelude::*;
+
+    fn event(id: &str) -> Event {
+        message(&format!("event {}", id), id)
+    }
+
+    fn next(replay: &mut Replay) -> Option<Option<Bytes>> {
+        replay.next().now_or_never()
+    }
+
+    #[test]
+    fn replays_then_live() {
+        let buffer = ReplayBuffer::new(3);
+        for id in ["1", "2", "3", "4"] {
+            buffer.send(&event(id)).unwrap();
+        }
+
+        let mut replay = buffer.subscribe(Some("2")).unwrap();
+        let mut live = buffer.subscribe(None).unwrap();
+        assert_eq!(
+            next(&mut replay),
+            Some(Some("id: 3\ndata: event 3\n\n".into()))
+        );
+        assert_eq!(
+            next(&mut replay),
+            Some(Some("id: 4\ndata: event 4\n\n".into()))
+        );
+        assert_eq!(next(&mut replay), None);
+        assert_eq!(next(&mut live), None);
+
+        buffer.send(&event("5")).unwrap();
+        assert_eq!(
+            next(&mut replay),
+            Some(Some("id: 5\ndata: event 5\n\n".into()))
+        );
+        assert_eq!(
+            next(&mut live),
+            Some(Some("id: 5\ndata: event 5\n\n".into()))
+        );
+
+        let mut evicted = buffer.subscribe(Some("1")).unwrap_err().into_live();
+        assert_eq!(next(&mut evicted), None);
+        assert!(buffer.subscribe(Some("unknown")).is_err());
+
+        buffer.send(&event("6")).unwrap();
+        assert_eq!(
+            next(&mut evicted),
+            Some(Some("id: 6\ndata: event 6\n\n".into()))
+        );
+
+        // A subscriber which falls behind ends
+        for id in ["7", "8", "9"] {
+            buffer.send(&event(id)).unwrap();
+        }
+        assert_eq!(next(&mut live), Some(None));
+        assert_eq!(buffer.lock().wakers.len(), 0);
+    }
+
+    #[test]
+    fn limits() {
+        let buffer = ReplayBuffer::new(100).with_max_bytes(50);
+        for id in ["1", "2", "3", "4"] {
+            buffer.send(&event(id)).unwrap();
+        }
+        // Each event takes 21 bytes
+        assert!(buffer.subscribe(Some("2")).is_err());
+        assert!(buffer.subscribe(Some("3")).is_ok());
+
+        let buffer = ReplayBuffer::new(100).with_max_age(Duration::from_millis(1));
+        buffer.send(&event("1")).unwrap();
+        buffer.send(&event("2")).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(buffer.subscribe(Some("1")).is_err());
+        // The newest event is kept
+        assert!(buffer.subscribe(Some("2")).is_ok());
+
+        let invalid = Event::<String> {
+            id: "1\n2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(buffer.send(&invalid), Err(EncodeError::InvalidId));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::backoff::ConstantBackoff;
+use crate::event_source::{Connector, EventSource};
+use crate::response::{set_request_headers, validate, ResponseError, MAX_BODY_SNIPPET};
+use crate::timer::TokioTimer;
+use bytes::Bytes;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use reqwest::RequestBuilder;
+
+/// Error of an [`EventSource`] connecting with reqwest
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReqwestError {
+    /// The request could not be built or sent, or the connection failed
+    Transport(reqwest::Error),
+    /// The request body is a stream, so the request can't be sent again to reconnect. Use
+    /// [`ReqwestConnector::from_fn`] to build it anew instead.
+    CannotCloneRequest,
+    /// The response does not start an event stream. An [`EventSource`] yields it as
+    /// [`crate::EventStreamError::Response`] instead.
+    Response(ResponseError),
+}
+
+impl From<reqwest::Error> for ReqwestError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Transport(err)
+    }
+}
+
+impl fmt::Display for ReqwestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(err) => f.write_fmt(format_args!("Transport error: {}", err)),
+            Self::CannotCloneRequest => f.write_str("Request can't be cloned to reconnect"),
+            Self::Response(err) => f.write_fmt(format_args!("Response error: {}", err)),
+        }
+    }
+}
+
+impl std::error::Error for ReqwestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(err) => Some(err),
+            Self::Response(err) => Some(err),
+            Self::CannotCloneRequest => None,
+        }
+    }
+}
+
+/// Body of an event stream response
+pub struct ResponseStream {
+    inner: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+}
+
+impl fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream").finish_non_exhaustive()
+    }
+}
+
+impl Stream for ResponseStream {
+    type Item = Result<Bytes, ReqwestError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|result| result.map_err(ReqwestError::Transport)))
+    }
+}
+
+/// Builds the request of every connection attempt of a [`ReqwestConnector`]
+type RequestFactory = Box<dyn FnMut(Option<&str>, u32) -> RequestBuilder + Send>;
+
+/// A [`Connector`] sending a reqwest request
+///
+/// Every connection attempt sends a copy of the request with the `Accept: text/event-stream`
+/// and `Cache-Control: no-cache` headers, as well as `Last-Event-ID` when resuming. Responses
+/// other than `200 OK` with a `Content-Type` of `text/event-stream` are yielded as
+/// [`crate::EventStreamError::Response`] and end the source, as does a request which can't be
+/// built.
+///
+/// Instead of a fixed request, [`ReqwestConnector::from_fn`] builds a new one for every
+/// attempt, for example to `POST` a body or to refresh an authorization token.
+pub struct ReqwestConnector {
+    factory: RequestFactory,
+}
+
+impl fmt::Debug for ReqwestConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReqwestConnector").finish_non_exhaustive()
+    }
+}
+
+impl ReqwestConnector {
+    /// Create a connector sending the request of `builder`
+    pub fn new(builder: RequestBuilder) -> Result<Self, ReqwestError> {
+        let (client, request) = builder.build_split();
+        let request = request?;
+        if request.try_clone().is_none() {
+            return Err(ReqwestError::CannotCloneRequest);
+        }
+        Ok(Self::from_fn(move |_, _| {
+            let request = request.try_clone().expect("checked to be cloneable");
+            RequestBuilder::from_parts(client.clone(), request)
+        }))
+    }
+
+    /// Create a connector sending the request built by `factory` on every attempt
+    ///
+    /// The factory is given the last event ID, if any, and the attempt number, which is 0 for
+    /// the first connection and otherwise counts the reconnects since the last healthy one.
+    /// The event stream headers are still added to the request, so the factory only needs to
+    /// use the last event ID if the server expects it elsewhere, such as in the body. As the
+    /// request is built anew, its body may also be a stream.
+    ///
+    /// ```no_run
+    /// use eventsource_stream::{EventSource, ReqwestConnector, TokioTimer};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let client = reqwest::Client::new();
+    /// let token = Arc::new(Mutex::new(String::from("initial token")));
+    /// let connector = ReqwestConnector::from_fn(move |last_event_id, attempt| {
+    ///     client
+    ///         .post("http://localhost:7020/completions")
+    ///         .bearer_auth(token.lock().unwrap().as_str())
+    ///         .header("Content-Type", "application/json")
+    ///         .body(format!(
+    ///             r#"{{"prompt": "Hello", "resume_after": {:?}, "attempt": {}}}"#,
+    ///             last_event_id.unwrap_or_default(),
+    ///             attempt
+    ///         ))
+    /// });
+    /// let source = EventSource::new(connector, TokioTimer);
+    /// ```
+    pub fn from_fn<F>(factory: F) -> Self
+    where
+        F: FnMut(Option<&str>, u32) -> RequestBuilder + Send + 'static,
+    {
+        Self {
+            factory: Box::new(factory),
+        }
+    }
+}
+
+impl Connector for ReqwestConnector {
+    type Stream = ResponseStream;
+    type Error = ReqwestError;
+    type Future = Pin<Box<dyn Future<Output = Result<ResponseStream, ReqwestError>> + Send>>;
+
+    fn connect(&mut self, last_event_id: Option<String>, attempt: u32) -> Self::Future {
+        let builder = (self.factory)(last_event_id.as_deref(), attempt);
+        let (client, request) = builder.build_split();
+        let mut request = match request {
+            Ok(request) => request,
+            Err(err) => return Box::pin(async { Err(err.into()) }),
+        };
+        set_request_headers(request.headers_mut(), last_event_id.as_deref());
+
+        let response = client.execute(request);
+        Box::pin(async move {
+            let mut response = response.await?;
+            if let Err(err) = validate(response.status(), response.headers()) {
+                let mut body = Vec::new();
+                while body.len() < MAX_BODY_SNIPPET {
+                    match response.chunk().await {
+                        Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                        _ => break,
+                    }
+                }
+                return Err(ReqwestError::Response(err.with_body(&body)));
+            }
+            Ok(ResponseStream {
+                inner: Box::pin(response.bytes_stream()),
+            })
+        })
+    }
+
+    fn is_fatal(&self, err: &ReqwestError) -> bool {
+        match err {
+            // A request which can't be built, such as one with an invalid URL, never will be
+            ReqwestError::Transport(err) => err.is_builder(),
+            ReqwestError::Response(_) | ReqwestError::CannotCloneRequest => true,
+        }
+    }
+
+    fn response_error(&self, err: ReqwestError) -> Result<ResponseError, ReqwestError> {
+        match err {
+            ReqwestError::Response(err) => Ok(err),
+            err => Err(err),
+        }
+    }
+}
+
+/// An [`EventSource`] connecting with reqwest, created with
+/// [`RequestBuilderExt::event_source`]
+pub type ReqwestEventSource<P = ConstantBackoff> = EventSource<ReqwestConnector, TokioTimer, P>;
+
+/// Extension of [`RequestBuilder`] for event streams
+pub trait RequestBuilderExt {
+    /// Create an [`EventSource`] which sends this request, reconnecting whenever the response
+    /// ends or fails
+    ///
+    /// ```no_run
+    /// use eventsource_stream::{RequestBuilderExt, SourceItem};
+    /// use futures::prelude::*;
+    ///
+    /// # async fn run() -> Result<(), eventsource_stream::ReqwestError> {
+    /// let source = reqwest::Client::new()
+    ///     .get("http://localhost:7020/notifications")
+    ///     .event_source()?;
+    /// futures::pin_mut!(source);
+    ///
+    /// while let Some(item) = source.next().await {
+    ///     if let Ok(SourceItem::Event(event)) = item {
+    ///         println!("{}", event.data);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn event_source(self) -> Result<ReqwestEventSource, ReqwestError>;
+}
+
+impl RequestBuilderExt for RequestBuilder {
+    fn event_source(self) -> Result<ReqwestEventSource, ReqwestError> {
+        Ok(EventSource::new(ReqwestConnector::new(self)?, TokioTimer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CloseReason, EventStreamError, ResponseErrorKind, SourceItem};
+    use core::time::Duration;
+    use futures::prelude::*;
+    use reqwest::StatusCode;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serve one canned response per connection, recording the requests
+    fn serve(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let mut len = usize::MAX;
+                while request.len() < len {
+                    let read = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                    let text = String::from_utf8_lossy(&request).to_lowercase();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let body = text
+                            .split("\r\n")
+                            .find_map(|line| line.strip_prefix("content-length: "))
+                            .map_or(0, |value| value.parse().unwrap());
+                        len = end + 4 + body;
+                    }
+                }
+                let request = String::from_utf8(request).unwrap().to_lowercase();
+                recorded.lock().unwrap().push(request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn reconnects_with_headers() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+             id: 1\ndata: first\n\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream; charset=utf-8\r\n\
+             Connection: close\r\n\r\ndata: second\n\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nContent-Length: 18\r\n\
+             Connection: close\r\n\r\n<h1>Not Found</h1>",
+        ]);
+
+        let items = reqwest::Client::new()
+            .get(url)
+            .header("Cache-Control", "max-age=60")
+            .event_source()
+            .unwrap()
+            .with_backoff(ConstantBackoff::new(Duration::from_millis(10)))
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut events = Vec::new();
+        let mut errors = Vec::new();
+        for item in &items {
+            match item {
+                Ok(SourceItem::Event(event)) => events.push(event),
+                Ok(_) => {}
+                Err(err) => errors.push(err),
+            }
+        }
+        assert_eq!(events[0].data, "first");
+        assert_eq!(events[1].data, "second");
+        assert_eq!(events[1].id, "1");
+        let err = match errors[..] {
+            [EventStreamError::Response(err)] => err,
+            _ => panic!("unexpected errors {:?}", errors),
+        };
+        assert!(!errors[0].is_transport());
+        assert_eq!(err.kind(), ResponseErrorKind::InvalidStatus);
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+        assert_eq!(err.body(), "<h1>Not Found</h1>");
+        assert!(matches!(
+            items.last(),
+            Some(Ok(SourceItem::Closed(CloseReason::Failed)))
+        ));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        for (index, request) in requests.iter().enumerate() {
+            assert!(request.contains("accept: text/event-stream\r\n"));
+            assert!(request.contains("cache-control: no-cache\r\n"));
+            assert!(!request.contains("max-age"));
+            assert_eq!(request.contains("last-event-id: 1\r\n"), index > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn no_content() {
+        let (url, _) = serve(vec!["HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"]);
+        let items = reqwest::Client::new()
+            .get(url)
+            .event_source()
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(
+            &items[..],
+            [
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Response(err)),
+                Ok(SourceItem::Closed(CloseReason::Failed)),
+            ] if err.kind() == ResponseErrorKind::NoContent
+        ));
+    }
+
+    #[tokio::test]
+    async fn factory() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+             id: 1\ndata: first\n\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+             data: second\n\n",
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        ]);
+
+        let client = reqwest::Client::new();
+        let mut token = 0;
+        let connector = ReqwestConnector::from_fn(move |last_event_id, attempt| {
+            token += 1;
+            client
+                .post(&url)
+                .bearer_auth(format!("token-{}", token))
+                .body(format!("{}/{}", last_event_id.unwrap_or("none"), attempt))
+        });
+        let events = EventSource::new(connector, TokioTimer)
+            .with_backoff(ConstantBackoff::new(Duration::from_millis(10)))
+            .filter_map(|item| async move {
+                match item {
+                    Ok(SourceItem::Event(event)) => Some(event.data),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events, vec!["first", "second"]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        let expected = [
+            ("authorization: bearer token-1\r\n", "\r\n\r\nnone/0"),
+            ("authorization: bearer token-2\r\n", "\r\n\r\n1/1"),
+            ("authorization: bearer token-3\r\n", "\r\n\r\n1/1"),
+        ];
+        for (request, (auth, body)) in requests.iter().zip(expected) {
+            assert!(request.starts_with("post /events "));
+            assert!(request.contains("accept: text/event-stream\r\n"));
+            assert!(request.contains(auth));
+            assert!(request.ends_with(body));
+        }
+        assert!(requests[1].contains("last-event-id: 1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn invalid_request() {
+        let client = reqwest::Client::new();
+        let connector = ReqwestConnector::from_fn(move |_, _| client.get("not a url"));
+        let items = EventSource::new(connector, TokioTimer)
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(
+            &items[..],
+            [
+                Ok(SourceItem::Connecting),
+                Err(EventStreamError::Transport(ReqwestError::Transport(err))),
+                Ok(SourceItem::Closed(CloseReason::Failed)),
+            ] if err.is_builder()
+        ));
+    }
+
+    #[test]
+    fn streaming_body() {
+        let body = reqwest::Body::wrap_stream(stream::iter(vec![Ok::<_, std::io::Error>("data")]));
+        let result = reqwest::Client::new()
+            .post("http://localhost/events")
+            .body(body)
+            .event_source();
+        assert!(matches!(result, Err(ReqwestError::CannotCloneRequest)));
+    }
+}
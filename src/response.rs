@@ -0,0 +1,233 @@
+use core::fmt;
+use http::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CACHE_CONTROL, CONTENT_TYPE};
+use http::response::Parts;
+use http::{Request, Response, StatusCode};
+
+const LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+const X_ACCEL_BUFFERING: HeaderName = HeaderName::from_static("x-accel-buffering");
+
+/// Headers of a response sending an event stream, see [`event_stream_response`]
+pub(crate) const RESPONSE_HEADERS: [(HeaderName, &str); 3] = [
+    (CONTENT_TYPE, "text/event-stream"),
+    (CACHE_CONTROL, "no-cache"),
+    (X_ACCEL_BUFFERING, "no"),
+];
+
+/// Maximum number of bytes of the response body kept in a [`ResponseError`]
+pub const MAX_BODY_SNIPPET: usize = 512;
+
+/// The reason a [`ResponseError`] occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResponseErrorKind {
+    /// The server responded with `204 No Content`, asking the client to stop reconnecting
+    NoContent,
+    /// The status was not `200 OK`
+    InvalidStatus,
+    /// The `Content-Type` was not `text/event-stream`
+    InvalidContentType,
+}
+
+impl fmt::Display for ResponseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoContent => f.write_str("server asked to stop reconnecting"),
+            Self::InvalidStatus => f.write_str("invalid response status"),
+            Self::InvalidContentType => f.write_str("response is not an event stream"),
+        }
+    }
+}
+
+/// Error for an HTTP response which does not start an event stream
+///
+/// As the HTML spec requires, a client should not reconnect after such a response. The error
+/// keeps the start of the response body, which often explains what went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseError {
+    kind: ResponseErrorKind,
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: String,
+}
+
+impl ResponseError {
+    /// Why the response was rejected
+    pub fn kind(&self) -> ResponseErrorKind {
+        self.kind
+    }
+
+    /// Status of the response
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// `Content-Type` of the response, if it had one
+    pub fn content_type(&self) -> Option<&HeaderValue> {
+        self.content_type.as_ref()
+    }
+
+    /// Up to [`MAX_BODY_SNIPPET`] bytes of the response body, lossily decoded. Empty unless
+    /// added with [`ResponseError::with_body`].
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Keep the start of the response body, which is truncated to [`MAX_BODY_SNIPPET`] bytes
+    pub fn with_body(mut self, body: &[u8]) -> Self {
+        let body = &body[..body.len().min(MAX_BODY_SNIPPET)];
+        self.body = String::from_utf8_lossy(body).into_owned();
+        self
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("{} ({})", self.kind, self.status))?;
+        if let Some(content_type) = &self.content_type {
+            f.write_fmt(format_args!(" of type {:?}", content_type))?;
+        }
+        if !self.body.is_empty() {
+            f.write_fmt(format_args!(": {:?}", self.body))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
+/// Check that a response starts an event stream, as described by the HTML spec
+///
+/// ```
+/// use eventsource_stream::{validate_response, ResponseErrorKind};
+///
+/// let response = http::Response::builder()
+///     .status(404)
+///     .header("Content-Type", "text/html")
+///     .body(())
+///     .unwrap();
+/// let (parts, _) = response.into_parts();
+/// let err = validate_response(&parts).unwrap_err();
+/// assert_eq!(err.kind(), ResponseErrorKind::InvalidStatus);
+/// ```
+pub fn validate_response(parts: &Parts) -> Result<(), ResponseError> {
+    validate(parts.status, &parts.headers)
+}
+
+pub(crate) fn validate(status: StatusCode, headers: &HeaderMap) -> Result<(), ResponseError> {
+    let content_type = headers.get(CONTENT_TYPE);
+    let kind = if status == StatusCode::NO_CONTENT {
+        ResponseErrorKind::NoContent
+    } else if status != StatusCode::OK {
+        ResponseErrorKind::InvalidStatus
+    } else if !content_type.is_some_and(is_event_stream) {
+        ResponseErrorKind::InvalidContentType
+    } else {
+        return Ok(());
+    };
+    Err(ResponseError {
+        kind,
+        status,
+        content_type: content_type.cloned(),
+        body: String::new(),
+    })
+}
+
+/// Prepare a request for an event stream, resuming after `last_event_id`
+///
+/// Sets the `Accept: text/event-stream` and `Cache-Control: no-cache` headers, and replaces
+/// the `Last-Event-ID` header. An ID which is not a valid header value is left out.
+///
+/// ```
+/// let mut request = http::Request::get("http://localhost:7020/notifications")
+///     .body(())
+///     .unwrap();
+/// eventsource_stream::prepare_request(&mut request, Some("42"));
+/// assert_eq!(request.headers()["accept"], "text/event-stream");
+/// assert_eq!(request.headers()["last-event-id"], "42");
+/// ```
+pub fn prepare_request<B>(request: &mut Request<B>, last_event_id: Option<&str>) {
+    set_request_headers(request.headers_mut(), last_event_id);
+}
+
+/// Build a `200 OK` response sending `body` as an event stream
+///
+/// Sets `Content-Type: text/event-stream`, `Cache-Control: no-cache` so that the events are not
+/// cached, and `X-Accel-Buffering: no` so that proxies like nginx pass every event on right
+/// away instead of buffering the body.
+///
+/// ```
+/// let response = eventsource_stream::event_stream_response("data: Hello, world!\n\n");
+/// assert_eq!(response.headers()["content-type"], "text/event-stream");
+/// assert_eq!(response.headers()["x-accel-buffering"], "no");
+/// ```
+pub fn event_stream_response<B>(body: B) -> Response<B> {
+    let mut response = Response::new(body);
+    for (name, value) in RESPONSE_HEADERS {
+        let value = HeaderValue::from_static(value);
+        response.headers_mut().insert(name, value);
+    }
+    response
+}
+
+pub(crate) fn set_request_headers(headers: &mut HeaderMap, last_event_id: Option<&str>) {
+    headers.insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    headers.remove(LAST_EVENT_ID);
+    if let Some(id) = last_event_id.and_then(|id| HeaderValue::from_str(id).ok()) {
+        headers.insert(LAST_EVENT_ID, id);
+    }
+}
+
+fn is_event_stream(content_type: &HeaderValue) -> bool {
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: u16, content_type: Option<&str>) -> Result<(), ResponseErrorKind> {
+        let mut response = http::Response::builder().status(status);
+        if let Some(content_type) = content_type {
+            response = response.header(CONTENT_TYPE, content_type);
+        }
+        let (parts, _) = response.body(()).unwrap().into_parts();
+        validate_response(&parts).map_err(|err| err.kind())
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(check(200, Some("text/event-stream")), Ok(()));
+        assert_eq!(check(200, Some("Text/Event-Stream; charset=utf-8")), Ok(()));
+        assert_eq!(check(204, None), Err(ResponseErrorKind::NoContent));
+        assert_eq!(
+            check(404, Some("text/event-stream")),
+            Err(ResponseErrorKind::InvalidStatus)
+        );
+        assert_eq!(
+            check(301, Some("text/event-stream")),
+            Err(ResponseErrorKind::InvalidStatus)
+        );
+        assert_eq!(
+            check(200, Some("text/html")),
+            Err(ResponseErrorKind::InvalidContentType)
+        );
+        assert_eq!(check(200, None), Err(ResponseErrorKind::InvalidContentType));
+    }
+
+    #[test]
+    fn body_snippet() {
+        let headers = HeaderMap::new();
+        let err = validate(StatusCode::BAD_GATEWAY, &headers).unwrap_err();
+        assert_eq!(err.to_string(), "invalid response status (502 Bad Gateway)");
+
+        let err = err.with_body(&[b'x'; 2 * MAX_BODY_SNIPPET]);
+        assert_eq!(err.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(err.body().len(), MAX_BODY_SNIPPET);
+        assert!(err.to_string().ends_with("xxx\""));
+    }
+}
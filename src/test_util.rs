@@ -0,0 +1,58 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+
+use crate::event::Event;
+
+/// An event with the given fields
+pub(crate) fn event(event: &str, data: &str, id: &str) -> Event {
+    Event {
+        event: event.to_string(),
+        data: data.to_string(),
+        id: id.to_string(),
+        ..Default::default()
+    }
+}
+
+/// An event of the default `message` type, as the parser yields it
+pub(crate) fn message(data: &str, id: &str) -> Event {
+    event("message", data, id)
+}
+
+/// An event stream with an invalid UTF8 sequence, a comment, a line of 16 bytes and a trailing
+/// partial event
+#[cfg(feature = "std")]
+pub(crate) const INPUT: &[u8] = b"id: 1\r\ndata: first\r\n\r\ndata: \xFF\n\n: comment\n\
+    data: 0123456789abcdef\n\ndata: second\n\ndata: trailing";
+
+/// A reader of its input a few bytes at a time, failing with the `errors` near the end
+#[cfg(feature = "std")]
+pub(crate) struct Trickle {
+    pub(crate) input: &'static [u8],
+    pub(crate) errors: Vec<std::io::ErrorKind>,
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.input.len() < 24 {
+            if let Some(kind) = self.errors.pop() {
+                return Err(kind.into());
+            }
+        }
+        let len = buf.len().min(self.input.len()).min(3);
+        buf[..len].copy_from_slice(&self.input[..len]);
+        self.input = &self.input[len..];
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncRead for Trickle {
+    fn poll_read(
+        self: core::pin::Pin<&mut Self>,
+        _cx: &mut core::task::Context,
+        buf: &mut [u8],
+    ) -> core::task::Poll<std::io::Result<usize>> {
+        core::task::Poll::Ready(std::io::Read::read(self.get_mut(), buf))
+    }
+}
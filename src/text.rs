@@ -0,0 +1,43 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+use private::Line;
+
+/// String type used for the fields of an [`crate::Event`]
+///
+/// Implemented for [`String`] and, with the `bytes` feature, for `ByteStr`. This trait
+/// is sealed and cannot be implemented outside of this crate.
+pub trait EventText: Default + Clone + AsRef<str> + From<String> + private::Sealed {}
+
+impl EventText for String {}
+
+impl private::Sealed for String {
+    fn from_field(_line: &Line, value: &str) -> Self {
+        value.to_string()
+    }
+
+    fn from_static(value: &'static str) -> Self {
+        value.to_string()
+    }
+}
+
+pub(crate) mod private {
+    #[cfg(feature = "bytes")]
+    use bytes::Bytes;
+
+    /// A line of the event stream as seen by the parser
+    pub struct Line<'a> {
+        /// Contents of the line without its end-of-line
+        pub text: &'a str,
+        /// The chunk the line was found in, if it can be shared rather than copied
+        #[cfg(feature = "bytes")]
+        pub shared: Option<&'a Bytes>,
+    }
+
+    pub trait Sealed {
+        /// Create a field value from a slice of `line`
+        fn from_field(line: &Line, value: &str) -> Self;
+
+        fn from_static(value: &'static str) -> Self;
+    }
+}
@@ -0,0 +1,63 @@
+use core::future::{self, Future, Pending};
+use core::time::Duration;
+
+/// Source of delays, so that the crate works with any async runtime
+///
+/// Implemented for closures returning a future which completes after the given duration, such
+/// as `tokio::time::sleep` or `async_io::Timer::after` wrapped to return `()`.
+///
+/// ```
+/// use eventsource_stream::Timer;
+/// use std::time::Duration;
+///
+/// fn assert_timer(_: impl Timer) {}
+///
+/// assert_timer(|duration: Duration| async move {
+///     // tokio::time::sleep(duration).await
+/// });
+/// ```
+pub trait Timer {
+    /// Future returned by [`Timer::sleep`]
+    type Sleep: Future<Output = ()>;
+
+    /// Create a future which completes after `duration`
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+}
+
+impl<F, Fut> Timer for F
+where
+    F: Fn(Duration) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Sleep = Fut;
+
+    fn sleep(&self, duration: Duration) -> Fut {
+        self(duration)
+    }
+}
+
+/// A [`Timer`] which never fires, used where no timer was configured
+#[derive(Default, Debug, Clone, Copy)]
+pub struct NoTimer;
+
+impl Timer for NoTimer {
+    type Sleep = Pending<()>;
+
+    fn sleep(&self, _duration: Duration) -> Pending<()> {
+        future::pending()
+    }
+}
+
+/// A [`Timer`] using [`tokio::time::sleep`]
+#[cfg(feature = "tokio")]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    type Sleep = tokio::time::Sleep;
+
+    fn sleep(&self, duration: Duration) -> tokio::time::Sleep {
+        tokio::time::sleep(duration)
+    }
+}
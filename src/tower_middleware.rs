@@ -0,0 +1,366 @@
+use crate::encoder::{EncodeError, EventEncoder};
+use crate::event::{Event, Frame};
+use crate::event_stream::{EventStream, EventStreamError, FrameStream};
+use crate::hyper_client::BodyStream;
+use crate::response::validate;
+use bytes::{Buf, Bytes};
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use futures_core::ready;
+use futures_core::stream::Stream;
+use futures_core::task::{Context, Poll};
+use http::header::CONTENT_LENGTH;
+use http::{Request, Response};
+use http_body::{Body, SizeHint};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A tower [`Layer`] rewriting the events of event stream responses
+///
+/// The body of every response which starts an event stream, as checked by
+/// [`crate::validate_response`], is parsed and each event is passed to the closure. The events
+/// it returns are encoded in its place, so returning `None` drops the event, `Some` maps it and
+/// a `Vec` can insert further events. Comments, such as keep-alives, and retry hints are passed
+/// on as they are. Every upstream frame is sent on as soon as it is parsed, and other responses
+/// are left untouched.
+///
+/// ```
+/// use eventsource_stream::{Event, EventLayer};
+///
+/// let layer = EventLayer::new(|event: Event| {
+///     (event.event != "internal").then(|| Event {
+///         data: event.data.to_uppercase(),
+///         ..event
+///     })
+/// });
+/// // let app = tower::ServiceBuilder::new().layer(layer).service(upstream);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventLayer<F> {
+    f: F,
+}
+
+impl<F> EventLayer<F> {
+    /// Rewrite the events of responses with `f`
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<S, F: Clone> Layer<S> for EventLayer<F> {
+    type Service = EventService<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        EventService {
+            inner,
+            f: self.f.clone(),
+        }
+    }
+}
+
+/// A service rewriting the events of the responses of another, created by an [`EventLayer`]
+#[derive(Debug, Clone)]
+pub struct EventService<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S, F, ReqBody, ResBody, I> Service<Request<ReqBody>> for EventService<S, F>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Body,
+    F: FnMut(Event) -> I + Clone,
+    I: IntoIterator<Item = Event>,
+{
+    type Response = Response<EventBody<ResBody, F>>;
+    type Error = S::Error;
+    type Future = EventFuture<S::Future, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        EventFuture {
+            future: self.inner.call(request),
+            f: Some(self.f.clone()),
+        }
+    }
+}
+
+pin_project! {
+/// The response future of an [`EventService`]
+#[derive(Debug)]
+pub struct EventFuture<Fut, F> {
+    #[pin]
+    future: Fut,
+    f: Option<F>,
+}
+}
+
+impl<Fut, F, B, E> Future for EventFuture<Fut, F>
+where
+    Fut: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<EventBody<B, F>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.project();
+        let response = ready!(this.future.poll(cx))?;
+        let f = this.f.take().expect("EventFuture polled after completion");
+        if validate(response.status(), response.headers()).is_err() {
+            return Poll::Ready(Ok(response.map(|body| EventBody {
+                kind: Kind::Passthrough { body },
+            })));
+        }
+
+        let (mut parts, body) = response.into_parts();
+        // The events are encoded anew, so their length may change
+        parts.headers.remove(CONTENT_LENGTH);
+        let frames = EventStream::new(BodyStream::new(body))
+            .with_lossy_utf8()
+            .frames();
+        let kind = Kind::Events {
+            frames,
+            f,
+            encoder: EventEncoder::new(),
+        };
+        Poll::Ready(Ok(Response::from_parts(parts, EventBody { kind })))
+    }
+}
+
+/// Error of an [`EventBody`]
+#[derive(Debug)]
+pub enum EventBodyError<E> {
+    /// The upstream body failed
+    Body(E),
+    /// An event returned by the closure can't be encoded
+    Encode(EncodeError),
+}
+
+impl<E: fmt::Display> fmt::Display for EventBodyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(err) => f.write_fmt(format_args!("Body error: {}", err)),
+            Self::Encode(err) => f.write_fmt(format_args!("Encode error: {}", err)),
+        }
+    }
+}
+
+impl<E> std::error::Error for EventBodyError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Body(err) => Some(err),
+            Self::Encode(err) => Some(err),
+        }
+    }
+}
+
+pin_project! {
+/// The body of a response of an [`EventService`]
+///
+/// Events the parser rejects are dropped, like a client would, while errors of the upstream
+/// body and events which can't be encoded fail the body.
+pub struct EventBody<B, F> {
+    #[pin]
+    kind: Kind<B, F>,
+}
+}
+
+pin_project! {
+#[project = KindProj]
+enum Kind<B, F> {
+    Events {
+        #[pin]
+        frames: FrameStream<BodyStream<B>>,
+        f: F,
+        encoder: EventEncoder,
+    },
+    Passthrough {
+        #[pin]
+        body: B,
+    },
+}
+}
+
+impl<B, F> fmt::Debug for EventBody<B, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBody").finish_non_exhaustive()
+    }
+}
+
+impl<B, F, I> Body for EventBody<B, F>
+where
+    B: Body,
+    F: FnMut(Event) -> I,
+    I: IntoIterator<Item = Event>,
+{
+    type Data = Bytes;
+    type Error = EventBodyError<B::Error>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<http_body::Frame<Bytes>, Self::Error>>> {
+        let (mut frames, f, encoder) = match self.project().kind.project() {
+            KindProj::Events { frames, f, encoder } => (frames, f, encoder),
+            KindProj::Passthrough { body } => {
+                let frame = ready!(body.poll_frame(cx));
+                return Poll::Ready(frame.map(|frame| match frame {
+                    Ok(frame) => {
+                        Ok(frame.map_data(|mut data| data.copy_to_bytes(data.remaining())))
+                    }
+                    Err(err) => Err(EventBodyError::Body(err)),
+                }));
+            }
+        };
+
+        loop {
+            let frame = match ready!(frames.as_mut().poll_next(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(EventStreamError::Transport(err))) => {
+                    return Poll::Ready(Some(Err(EventBodyError::Body(err))))
+                }
+                Some(Err(_)) => continue,
+                None => return Poll::Ready(None),
+            };
+
+            let mut buf = String::new();
+            match frame {
+                Frame::Event(event) => {
+                    for event in f(event) {
+                        if let Err(err) = encoder.encode_into(&event, &mut buf) {
+                            return Poll::Ready(Some(Err(EventBodyError::Encode(err))));
+                        }
+                    }
+                }
+                frame => {
+                    let _ = encoder.encode_frame_into(&frame, &mut buf);
+                    if let Frame::Comment(_) = frame {
+                        buf.push('\n');
+                    }
+                }
+            }
+            if !buf.is_empty() {
+                return Poll::Ready(Some(Ok(http_body::Frame::data(buf.into()))));
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.kind {
+            Kind::Events { .. } => false,
+            Kind::Passthrough { body } => body.is_end_stream(),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.kind {
+            Kind::Events { .. } => SizeHint::default(),
+            Kind::Passthrough { body } => body.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use futures::prelude::*;
+    use http_body_util::{BodyExt, StreamBody};
+    use tower::{service_fn, ServiceExt};
+
+    fn upstream(
+        content_type: &'static str,
+        chunks: Vec<&'static str>,
+    ) -> impl Service<
+        Request<()>,
+        Response = Response<impl Body<Data = Bytes, Error = Infallible>>,
+        Error = Infallible,
+    > {
+        service_fn(move |_| {
+            let frames = chunks
+                .clone()
+                .into_iter()
+                .map(|chunk| Ok(http_body::Frame::data(Bytes::from(chunk))));
+            let response = Response::builder()
+                .header("content-type", content_type)
+                .header("content-length", "1000")
+                .body(StreamBody::new(stream::iter(frames)))
+                .unwrap();
+            future::ok(response)
+        })
+    }
+
+    fn rewrite(event: Event) -> Vec<Event> {
+        match event.event.as_str() {
+            "internal" => vec![],
+            "split" => event
+                .data
+                .split(',')
+                .map(|data| Event {
+                    data: data.to_string(),
+                    ..event.clone()
+                })
+                .collect(),
+            _ => vec![Event {
+                data: event.data.to_uppercase(),
+                ..event
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn rewrites_events() {
+        let chunks = vec![
+            "id: 1\ndata: first\n\n:keep-alive\n\n",
+            "event: internal\ndata: secret\n\nretry: 500\n\n",
+            "event: split\ndata: a,b\n\n",
+        ];
+        let service = EventLayer::new(rewrite).layer(upstream("text/event-stream", chunks));
+        let response = service.oneshot(Request::new(())).await.unwrap();
+        assert!(response.headers().get(CONTENT_LENGTH).is_none());
+
+        let mut body = response.into_body();
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(
+            frames,
+            vec![
+                Bytes::from_static(b"id: 1\ndata: FIRST\n\n"),
+                Bytes::from_static(b":keep-alive\n\n"),
+                Bytes::from_static(b"retry: 500\n\n"),
+                Bytes::from_static(b"event: split\ndata: a\n\nevent: split\ndata: b\n\n"),
+            ]
+        );
+
+        let service = EventLayer::new(|event: Event| {
+            Some(Event {
+                event: "a\nb".to_string(),
+                ..event
+            })
+        })
+        .layer(upstream("text/event-stream", vec!["data: first\n\n"]));
+        let mut body = service.oneshot(Request::new(())).await.unwrap().into_body();
+        assert!(matches!(
+            body.frame().await,
+            Some(Err(EventBodyError::Encode(EncodeError::InvalidEventType)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn passes_other_responses() {
+        let service = EventLayer::new(rewrite).layer(upstream("application/json", vec!["{}"]));
+        let response = service.oneshot(Request::new(())).await.unwrap();
+        assert_eq!(response.headers()[CONTENT_LENGTH], "1000");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "{}");
+    }
+}
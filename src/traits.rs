@@ -1,3 +1,15 @@
+#[cfg(feature = "bytes")]
+use crate::byte_str::ByteStr;
+
+#[cfg(feature = "bytes")]
+use crate::encode_stream::EncodeStream;
+#[cfg(feature = "bytes")]
+use crate::event::Event;
+#[cfg(feature = "bytes")]
+use crate::timer::NoTimer;
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
 use crate::event_stream::EventStream;
 use futures_core::stream::Stream;
 
@@ -16,3 +28,41 @@ where
         EventStream::new(self)
     }
 }
+
+/// Entrypoint for creating zero-copy [`crate::Event`] streams, whose fields are [`ByteStr`]s
+#[cfg(feature = "bytes")]
+pub trait SharedEventsource: Sized {
+    /// Create a zero-copy event stream from a stream of chunks convertible into [`Bytes`],
+    /// such as the body of a reqwest or hyper response
+    fn eventsource_shared(self) -> EventStream<Self, ByteStr>;
+}
+
+#[cfg(feature = "bytes")]
+impl<S, B, E> SharedEventsource for S
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Into<Bytes>,
+{
+    fn eventsource_shared(self) -> EventStream<Self, ByteStr> {
+        EventStream::new_shared(self)
+    }
+}
+
+/// Entrypoint for encoding a stream of [`Event`]s into a stream of bytes, the reverse of
+/// [`Eventsource`]
+#[cfg(feature = "bytes")]
+pub trait EncodeEventsource: Sized {
+    /// Create a stream of encoded event stream bytes from a stream of events
+    fn encode_eventsource(self) -> EncodeStream<Self, NoTimer>;
+}
+
+#[cfg(feature = "bytes")]
+impl<S, T> EncodeEventsource for S
+where
+    S: Stream<Item = Event<T>>,
+    T: AsRef<str>,
+{
+    fn encode_eventsource(self) -> EncodeStream<Self, NoTimer> {
+        EncodeStream::new(self)
+    }
+}